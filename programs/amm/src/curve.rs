@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::instructions::{get_amount_in, get_amount_out};
+use crate::math::*;
+use crate::stable_swap::*;
+use crate::state::*;
+use crate::weighted_math::*;
+
+// Swap and LP math dispatched on Pool::curve_type. Later deposits and every
+// withdrawal are pro-rata on all curves, since scaling both reserves by the
+// same factor scales each invariant with them and leaves the price as it is.
+
+// Output of a swap of `amount_in`, fee included, on the pool's curve
+pub fn quote_amount_out(
+    pool: &Pool,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    zero_for_one: bool,
+    timestamp: i64,
+) -> Result<u64> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => {
+            get_amount_out(amount_in, reserve_in, reserve_out, pool.fee_bps)
+        }
+        CurveType::StableSwap => get_stable_amount_out(
+            amount_in,
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool.get_amp(timestamp),
+        ),
+        CurveType::Weighted => {
+            let (weight_in, weight_out) = pool.get_weights(zero_for_one);
+            get_weighted_amount_out(
+                amount_in,
+                reserve_in,
+                reserve_out,
+                pool.fee_bps,
+                weight_in,
+                weight_out,
+            )
+        }
+    }
+}
+
+// Smallest input, fee included, that buys `amount_out` on the pool's curve
+pub fn quote_amount_in(
+    pool: &Pool,
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    zero_for_one: bool,
+    timestamp: i64,
+) -> Result<u64> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => {
+            get_amount_in(amount_out, reserve_in, reserve_out, pool.fee_bps)
+        }
+        CurveType::StableSwap => get_stable_amount_in(
+            amount_out,
+            reserve_in,
+            reserve_out,
+            pool.fee_bps,
+            pool.get_amp(timestamp),
+        ),
+        CurveType::Weighted => {
+            let (weight_in, weight_out) = pool.get_weights(zero_for_one);
+            get_weighted_amount_in(
+                amount_out,
+                reserve_in,
+                reserve_out,
+                pool.fee_bps,
+                weight_in,
+                weight_out,
+            )
+        }
+    }
+}
+
+// LP for the first deposit, before MINIMUM_LIQUIDITY is taken off: the
+// invariant of the deposited amounts, sqrt(a * b), D or a^w0 * b^w1
+pub fn get_initial_liquidity(
+    pool: &Pool,
+    amount0: u64,
+    amount1: u64,
+    timestamp: i64,
+) -> Result<u64> {
+    match pool.curve_type {
+        CurveType::ConstantProduct => sqrt_product(amount0, amount1),
+        CurveType::StableSwap => {
+            let d = compute_d(pool.get_amp(timestamp), amount0, amount1)?;
+            u64::try_from(d).map_err(|_| error!(CustomError::MathOverflow))
+        }
+        CurveType::Weighted => get_weighted_invariant(pool, amount0, amount1),
+    }
+}
+
+// Checks that the invariant of the balances, each less the fee on what was
+// paid in, is no lower than that of the reserves before the swap
+pub fn check_invariant(
+    pool: &Pool,
+    reserves: (u64, u64),
+    balances: (u64, u64),
+    amounts_in: (u64, u64),
+    timestamp: i64,
+) -> Result<()> {
+    let (reserve0, reserve1) = reserves;
+    let fee_bps = pool.fee_bps as u128;
+    // scaled by FEE_DENOMINATOR so nothing is rounded
+    let balance0_adjusted =
+        balances.0 as u128 * FEE_DENOMINATOR as u128 - amounts_in.0 as u128 * fee_bps;
+    let balance1_adjusted =
+        balances.1 as u128 * FEE_DENOMINATOR as u128 - amounts_in.1 as u128 * fee_bps;
+    // the other invariants are homogeneous in the balances, so flooring the
+    // adjusted balances back to token units only makes the check stricter
+    let balance0 = (balance0_adjusted / FEE_DENOMINATOR as u128) as u64;
+    let balance1 = (balance1_adjusted / FEE_DENOMINATOR as u128) as u64;
+
+    let holds = match pool.curve_type {
+        CurveType::ConstantProduct => {
+            full_mul(balance0_adjusted, balance1_adjusted)
+                >= full_mul(
+                    reserve0 as u128 * reserve1 as u128,
+                    (FEE_DENOMINATOR * FEE_DENOMINATOR) as u128,
+                )
+        }
+        CurveType::StableSwap => {
+            let amp = pool.get_amp(timestamp);
+            compute_d(amp, balance0, balance1)? >= compute_d(amp, reserve0, reserve1)?
+        }
+        CurveType::Weighted => {
+            weighted_invariant_holds(pool, (reserve0, reserve1), (balance0, balance1))?
+        }
+    };
+
+    if !holds {
+        return err!(CustomError::InvariantViolated);
+    }
+
+    Ok(())
+}
@@ -12,4 +12,62 @@ pub enum CustomError {
     MathOverflow,
     #[msg("Deposit Zero amount token")]
     ZeroAmount,
+    #[msg("Fee exceeds the maximum allowed")]
+    InvalidFee,
+    #[msg("Slippage tolerance exceeded")]
+    SlippageExceeded,
+    #[msg("Minted liquidity is below the minimum requested")]
+    InsufficientLiquidityOut,
+    #[msg("Withdrawn token0 amount is below the minimum requested")]
+    InsufficientAmount0Out,
+    #[msg("Withdrawn token1 amount is below the minimum requested")]
+    InsufficientAmount1Out,
+    #[msg("Mint has a freeze authority")]
+    MintHasFreezeAuthority,
+    #[msg("Mint has a permanent delegate")]
+    MintHasPermanentDelegate,
+    #[msg("Mint has a transfer hook")]
+    MintHasTransferHook,
+    #[msg("Mint is non-transferable")]
+    MintNonTransferable,
+    #[msg("Pool is paused")]
+    PoolPaused,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Signer lacks the required role")]
+    MissingRole,
+    #[msg("Unknown role")]
+    InvalidRole,
+    #[msg("Pool creation is restricted to pool creators")]
+    PermissionlessCreationDisabled,
+    #[msg("Pool is delisted")]
+    PoolDelisted,
+    #[msg("Observation capacity must grow and stay within the maximum")]
+    InvalidObservationCapacity,
+    #[msg("Requested timestamp is older than the oldest observation")]
+    ObservationTooOld,
+    #[msg("Invalid TWAP window")]
+    InvalidTimeWindow,
+    #[msg("Route accounts don't form a valid path")]
+    InvalidRoute,
+    #[msg("A flash loan is open on this pool")]
+    FlashLoanActive,
+    #[msg("No flash loan is open on this pool")]
+    FlashLoanNotActive,
+    #[msg("Flash loan isn't repaid later in the transaction")]
+    FlashLoanNotRepaid,
+    #[msg("Pool invariant doesn't hold after the swap")]
+    InvariantViolated,
+    #[msg("Callback program can't be this program")]
+    InvalidCallbackProgram,
+    #[msg("Amplification is out of range")]
+    InvalidAmp,
+    #[msg("Amplification ramp is too short or moves A too far")]
+    InvalidAmpRamp,
+    #[msg("Pool isn't a StableSwap pool")]
+    NotStablePool,
+    #[msg("Curve invariant didn't converge")]
+    CurveNotConverged,
+    #[msg("Pool weights are out of range")]
+    InvalidWeight,
 }
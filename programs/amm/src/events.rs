@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{CurveType, PoolStatus};
+
+// Reserves in these events are the post-instruction vault balances minus
+// uncollected protocol fees, i.e. what the next swap is priced on
+
+#[event]
+pub struct AdminInitialized {
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminProposalCancelled {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminAccepted {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct PoolCreated {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub fee_bps: u16,
+    // lamports paid to the treasury, 0 for pool creators
+    pub creation_fee: u64,
+    pub curve_type: CurveType,
+    pub weight0: u16,
+    pub weight1: u16,
+}
+
+// Amounts are what left the depositor, before Token-2022 transfer fees
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub liquidity_minted: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+// Amounts are what left the vaults, before Token-2022 transfer fees; the
+// received amounts are what reached the depositor after them
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub amount0_received: u64,
+    pub amount1_received: u64,
+    pub liquidity_burned: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+// amount_in left the swapper and amount_out left the vault, both before
+// Token-2022 transfer fees; amount_out_received is what reached the
+// recipient after them. protocol_fee is the part of swap_fee set aside for
+// the treasury.
+#[event]
+pub struct Swapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub token_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub amount_out_received: u64,
+    pub swap_fee: u64,
+    pub protocol_fee: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+// Emitted by flash_repay. Amounts are what left the vaults and fees what came
+// back on top of them.
+#[event]
+pub struct FlashLoanRepaid {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub fee0: u64,
+    pub fee1: u64,
+}
+
+// Amounts out left the vaults before the callback, amounts in are what the
+// vaults gained by the time it returned
+#[event]
+pub struct FlashSwapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub amount0_out: u64,
+    pub amount1_out: u64,
+    pub amount0_in: u64,
+    pub amount1_in: u64,
+    pub protocol_fee0: u64,
+    pub protocol_fee1: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[event]
+pub struct AmpRampStarted {
+    pub pool: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_ramp_start: i64,
+    pub amp_ramp_end: i64,
+}
+
+// Amounts are what left the vaults, before Token-2022 transfer fees
+#[event]
+pub struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub treasury_withdrawer: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+}
+
+// Emitted by set_pool_status and delist_pool
+#[event]
+pub struct PoolStatusChanged {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub status: PoolStatus,
+}
+
+// `roles` are the bits granted or revoked, `holder_roles` what the holder
+// is left with
+#[event]
+pub struct RoleGranted {
+    pub holder: Pubkey,
+    pub roles: u8,
+    pub holder_roles: u8,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub holder: Pubkey,
+    pub roles: u8,
+    pub holder_roles: u8,
+}
+
+#[event]
+pub struct ProtocolFeeChanged {
+    pub fee_manager: Pubkey,
+    pub protocol_fee_bps: u16,
+}
+
+#[event]
+pub struct GlobalPauseChanged {
+    pub pauser: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct PoolCreationConfigChanged {
+    pub admin: Pubkey,
+    pub permissionless: bool,
+    pub creation_fee: u64,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct MintAllowed {
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct MintDisallowed {
+    pub mint: Pubkey,
+}
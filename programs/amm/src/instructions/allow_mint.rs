@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::error::*;
+use crate::events::*;
+use crate::state::*;
+
+pub fn allow_mint(ctx: Context<AllowMint>) -> Result<()> {
+    let allowed_mint = &mut ctx.accounts.allowed_mint;
+    allowed_mint.mint = ctx.accounts.mint.key();
+
+    emit!(MintAllowed {
+        mint: allowed_mint.mint,
+    });
+    Ok(())
+}
+
+pub fn disallow_mint(ctx: Context<DisallowMint>) -> Result<()> {
+    emit!(MintDisallowed {
+        mint: ctx.accounts.allowed_mint.mint,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AllowMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AllowedMint::INIT_SPACE,
+        seeds = [b"allowed_mint", mint.key().as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    #[account(
+        has_one = admin @ CustomError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisallowMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"allowed_mint", allowed_mint.mint.as_ref()],
+        bump
+    )]
+    pub allowed_mint: Account<'info, AllowedMint>,
+
+    #[account(
+        has_one = admin @ CustomError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+}
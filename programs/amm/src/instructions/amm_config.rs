@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::state::*;
+
+pub fn create_amm_config(ctx: Context<AmmConfigCreation>, fee_bps: u16) -> Result<()> {
+    if fee_bps > Pool::MAX_FEE_BPS {
+        return err!(CustomError::InvalidFee);
+    }
+
+    ctx.accounts.amm_config.fee_bps = fee_bps;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(fee_bps: u16)]
+pub struct AmmConfigCreation<'info> {
+    #[account(mut)]
+    pub fee_manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", fee_manager.key().as_ref()],
+        bump,
+        constraint = fee_manager_role.has(RoleAssignment::FEE_MANAGER) @ CustomError::MissingRole,
+    )]
+    pub fee_manager_role: Account<'info, RoleAssignment>,
+
+    // one config per fee rate, so a tier's fee never changes under its pools
+    #[account(
+        init,
+        payer = fee_manager,
+        space = 8 + AmmConfig::INIT_SPACE,
+        seeds = [b"amm_config", fee_bps.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub amm_config: Account<'info, AmmConfig>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::error::*;
+use crate::events::*;
+use crate::state::*;
+
+// Sends the protocol fees accrued by the pool to the treasury accounts
+pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+    let amount0 = ctx.accounts.pool.protocol_fees_token0;
+    let amount1 = ctx.accounts.pool.protocol_fees_token1;
+
+    let pool_key = ctx.accounts.pool.key();
+    let token0_key = ctx.accounts.token0.key();
+    let token1_key = ctx.accounts.token1.key();
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        "pool_authority".as_bytes(),
+        pool_key.as_ref(),
+        token0_key.as_ref(),
+        token1_key.as_ref(),
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    if amount0 > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token0_program.to_account_info(),
+                TransferChecked {
+                    mint: ctx.accounts.token0.to_account_info(),
+                    from: ctx.accounts.token0_vault.to_account_info(),
+                    to: ctx.accounts.treasury_account_0.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount0,
+            ctx.accounts.token0.decimals,
+        )?;
+    }
+
+    if amount1 > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token1_program.to_account_info(),
+                TransferChecked {
+                    mint: ctx.accounts.token1.to_account_info(),
+                    from: ctx.accounts.token1_vault.to_account_info(),
+                    to: ctx.accounts.treasury_account_1.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount1,
+            ctx.accounts.token1.decimals,
+        )?;
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.protocol_fees_token0 = 0;
+    pool.protocol_fees_token1 = 0;
+
+    emit!(ProtocolFeesCollected {
+        pool: pool_key,
+        treasury_withdrawer: ctx.accounts.treasury_withdrawer.key(),
+        amount0,
+        amount1,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    pub treasury_withdrawer: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", treasury_withdrawer.key().as_ref()],
+        bump,
+        constraint = treasury_withdrawer_role.has(RoleAssignment::TREASURY_WITHDRAWER)
+            @ CustomError::MissingRole,
+    )]
+    pub treasury_withdrawer_role: Account<'info, RoleAssignment>,
+
+    #[account(seeds = [b"admin"], bump)]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(mint::token_program = token0_program)]
+    pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
+    pub token1: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
+        bump,
+        has_one = token0,
+        has_one = token1,
+        constraint = !pool.flash_loan_active @ CustomError::FlashLoanActive,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: read only
+    #[account(
+        seeds = [b"pool_authority", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token0,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
+    )]
+    pub token0_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token1,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
+    )]
+    pub token1_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // owned by the treasury set by the admin; the withdrawer only triggers
+    // the transfer
+    #[account(
+        mut,
+        token::mint = token0,
+        token::authority = admin_settings.treasury,
+        token::token_program = token0_program,
+    )]
+    pub treasury_account_0: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token1,
+        token::authority = admin_settings.treasury,
+        token::token_program = token1_program,
+    )]
+    pub treasury_account_1: InterfaceAccount<'info, TokenAccount>,
+
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+}
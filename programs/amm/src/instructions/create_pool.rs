@@ -1,39 +1,232 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::Token,
-    token_interface::{self, Mint, Token2022, TokenAccount},
+    token_2022::{
+        spl_token_2022::{
+            extension::{
+                transfer_hook::TransferHook, BaseStateWithExtensions, ExtensionType,
+                StateWithExtensions,
+            },
+            state::Mint as MintState,
+        },
+        Token2022,
+    },
+    token_interface::{self, Mint, MintTo, TokenAccount, TokenInterface},
 };
 
+use crate::error::*;
+use crate::events::*;
 use crate::state::*;
 
-pub fn create_pool(ctx: Context<PoolCreation>) -> Result<()> {
+pub fn create_pool(mut ctx: Context<PoolCreation>) -> Result<()> {
+    initialize_pool(&mut ctx, CurveType::ConstantProduct, Pool::EQUAL_WEIGHT)
+}
+
+// Same as create_pool on the StableSwap curve, starting at amplification
+// `amp`. Both mints need the same decimals since the curve prices 1:1 in raw
+// units.
+pub fn create_stable_pool(mut ctx: Context<PoolCreation>, amp: u64) -> Result<()> {
+    if !(Pool::MIN_AMP..=Pool::MAX_AMP).contains(&amp) {
+        return err!(CustomError::InvalidAmp);
+    }
+    if ctx.accounts.token0.decimals != ctx.accounts.token1.decimals {
+        return err!(CustomError::DecimalsNotEqual);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.pool;
+    pool.amp_initial = amp;
+    pool.amp_target = amp;
+    pool.amp_ramp_start = timestamp;
+    pool.amp_ramp_end = timestamp;
+
+    initialize_pool(&mut ctx, CurveType::StableSwap, Pool::EQUAL_WEIGHT)
+}
+
+// Same as create_pool on the weighted product curve, with `weight0` bps of
+// the pool's value in token0 and the rest in token1, e.g. 8_000 for 80/20
+pub fn create_weighted_pool(mut ctx: Context<PoolCreation>, weight0: u16) -> Result<()> {
+    if !(Pool::MIN_WEIGHT..=Pool::WEIGHT_DENOMINATOR - Pool::MIN_WEIGHT).contains(&weight0) {
+        return err!(CustomError::InvalidWeight);
+    }
+
+    initialize_pool(&mut ctx, CurveType::Weighted, weight0)
+}
+
+fn initialize_pool(
+    ctx: &mut Context<PoolCreation>,
+    curve_type: CurveType,
+    weight0: u16,
+) -> Result<()> {
+    let is_pool_creator = ctx
+        .accounts
+        .creator_role
+        .as_ref()
+        .is_some_and(|role| role.has(RoleAssignment::POOL_CREATOR));
+
+    let mut creation_fee = 0;
+    if !is_pool_creator {
+        let admin_settings = &ctx.accounts.admin_settings;
+        if !admin_settings.permissionless_pool_creation {
+            return err!(CustomError::PermissionlessCreationDisabled);
+        }
+
+        creation_fee = admin_settings.pool_creation_fee;
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            creation_fee,
+        )?;
+    }
+
+    if ctx.accounts.token0_allowlist.is_none() {
+        validate_mint(&ctx.accounts.token0)?;
+    }
+    if ctx.accounts.token1_allowlist.is_none() {
+        validate_mint(&ctx.accounts.token1)?;
+    }
+
     let pool_settings = &mut ctx.accounts.pool;
 
+    pool_settings.amm_config = ctx.accounts.amm_config.key();
     pool_settings.token0 = ctx.accounts.token0.key();
     pool_settings.token1 = ctx.accounts.token1.key();
-    pool_settings.liquidity = 0;
+    pool_settings.liquidity = MINIMUM_LIQUIDITY;
+    pool_settings.fee_bps = ctx.accounts.amm_config.fee_bps;
+    pool_settings.curve_type = curve_type;
+    pool_settings.weight0 = weight0;
+    pool_settings.weight1 = Pool::WEIGHT_DENOMINATOR - weight0;
+    pool_settings.last_update_timestamp = Clock::get()?.unix_timestamp;
+
+    let observations = &mut ctx.accounts.observations;
+    observations.pool = ctx.accounts.pool.key();
+    observations.cardinality = 1;
+    observations.observations = vec![Observation {
+        timestamp: ctx.accounts.pool.last_update_timestamp,
+        initialized: true,
+        ..Default::default()
+    }];
+
+    let pool_key = ctx.accounts.pool.key();
+    let token0_key = ctx.accounts.token0.key();
+    let token1_key = ctx.accounts.token1.key();
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        "pool_authority".as_bytes(),
+        pool_key.as_ref(),
+        token0_key.as_ref(),
+        token1_key.as_ref(),
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    // Locking MINIMUM_LIQUIDITY forever makes the first-depositor share inflation unprofitable
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.token_liq.to_account_info(),
+                to: ctx.accounts.locked_liquidity.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        MINIMUM_LIQUIDITY,
+    )?;
+
+    emit!(PoolCreated {
+        pool: pool_key,
+        creator: ctx.accounts.creator.key(),
+        amm_config: ctx.accounts.amm_config.key(),
+        token0: token0_key,
+        token1: token1_key,
+        fee_bps: ctx.accounts.pool.fee_bps,
+        creation_fee,
+        curve_type,
+        weight0,
+        weight1: Pool::WEIGHT_DENOMINATOR - weight0,
+    });
+
+    Ok(())
+}
+
+// A frozen vault, a delegate that can move vault funds, a hook the swap CPIs
+// don't forward accounts for, or a token that can't leave the vault would
+// each lock or drain the pool
+fn validate_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    if mint.freeze_authority.is_some() {
+        return err!(CustomError::MintHasFreezeAuthority);
+    }
+
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != Token2022::id() {
+        return Ok(());
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    for extension in mint_state.get_extension_types()? {
+        match extension {
+            ExtensionType::PermanentDelegate => return err!(CustomError::MintHasPermanentDelegate),
+            ExtensionType::NonTransferable => return err!(CustomError::MintNonTransferable),
+            ExtensionType::TransferHook => {
+                // a hook without a program is inert
+                let hook = mint_state.get_extension::<TransferHook>()?;
+                if Option::<Pubkey>::from(hook.program_id).is_some() {
+                    return err!(CustomError::MintHasTransferHook);
+                }
+            }
+            _ => {}
+        }
+    }
+
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct PoolCreation<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub creator: Signer<'info>,
 
+    #[account(mint::token_program = token0_program)]
     pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
     pub token1: InterfaceAccount<'info, Mint>,
 
+    pub amm_config: Account<'info, AmmConfig>,
+
     #[account(
         init,
-        payer = admin,
-        seeds = [b"pool", token0.key().as_ref(), token1.key().as_ref()],
+        payer = creator,
+        seeds = [
+            b"pool",
+            amm_config.key().as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
         space = 8 + Pool::INIT_SPACE,
         bump,
         constraint = token0.key() < token1.key()
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        init,
+        payer = creator,
+        seeds = [b"observations", pool.key().as_ref()],
+        space = 8 + Observations::space(1),
+        bump
+    )]
+    pub observations: Account<'info, Observations>,
+
     /// CHECK: READ ONLY
     #[account(
         seeds = [
@@ -48,40 +241,64 @@ pub struct PoolCreation<'info> {
 
     #[account(
         init,
-        payer = admin,
+        payer = creator,
         associated_token::mint = token0,
         associated_token::authority = pool_authority,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token0_program,
     )]
     pub token0_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         init,
-        payer = admin,
+        payer = creator,
         associated_token::mint = token1,
         associated_token::authority = pool_authority,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token1_program,
     )]
     pub token1_vault: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(
-        has_one = admin,
-        seeds = [b"admin"],
-        bump
-    )]
+    #[account(seeds = [b"admin"], bump)]
     pub admin_settings: Account<'info, AdminSettings>,
 
+    // absent for permissionless creation
+    #[account(seeds = [b"role", creator.key().as_ref()], bump)]
+    pub creator_role: Option<Account<'info, RoleAssignment>>,
+
     #[account(
         init,
-        payer = admin,
+        payer = creator,
         seeds = [b"tokenliq", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
         bump,
-        mint::decimals = 9,
+        // sqrt(a * b) is denominated in 10^((d0 + d1) / 2) units
+        mint::decimals = ((token0.decimals as u16 + token1.decimals as u16) / 2) as u8,
         mint::authority = pool_authority,
+        mint::token_program = token_program,
     )]
     pub token_liq: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = token_liq,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token_program,
+    )]
+    pub locked_liquidity: InterfaceAccount<'info, TokenAccount>,
+
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+    // program of the LP mint, either of the two token programs
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+
+    // present when the admin has allowlisted the mint, skipping its checks
+    #[account(seeds = [b"allowed_mint", token0.key().as_ref()], bump)]
+    pub token0_allowlist: Option<Account<'info, AllowedMint>>,
+    #[account(seeds = [b"allowed_mint", token1.key().as_ref()], bump)]
+    pub token1_allowlist: Option<Account<'info, AllowedMint>>,
+
+    /// CHECK: receives the creation fee, checked against admin_settings
+    #[account(mut, address = admin_settings.treasury)]
+    pub treasury: AccountInfo<'info>,
 }
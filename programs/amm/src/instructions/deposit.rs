@@ -1,67 +1,137 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::Token,
-    token_interface::{self, Mint, MintTo, Token2022, TokenAccount, TransferChecked},
+    token_interface::{self, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked},
 };
-use fixed::types::I64F64;
 
+use crate::curve::*;
 use crate::error::*;
+use crate::events::*;
+use crate::math::*;
 use crate::state::*;
 
 // dodaj liq
 // wymintuj tokeny odpowiednie
 // pobierz od usera dwa tokeny
 
-pub fn deposit(ctx: Context<DepositLiquidity>, mut amount_a: u64, mut amount_b: u64) -> Result<()> {
+// Largest (amount_a, amount_b) within the maxima that matches the reserve ratio,
+// and the LP minted for it, for a constant product pool. The first deposit sets
+// the ratio and gets sqrt(a*b) - MINIMUM_LIQUIDITY, the rest of it is locked at
+// pool creation. Later ones get min(a * supply / reserve0, b * supply / reserve1).
+pub fn quote_deposit(
+    max_amount_a: u64,
+    max_amount_b: u64,
+    reserve0: u64,
+    reserve1: u64,
+    supply: u64,
+) -> Result<(u64, u64, u64)> {
+    if supply <= MINIMUM_LIQUIDITY {
+        let invariant = sqrt_product(max_amount_a, max_amount_b)?;
+        return quote_first_deposit(max_amount_a, max_amount_b, invariant);
+    }
+
+    if reserve0 == 0 || reserve1 == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let amount_b_optimal = mul_div(max_amount_a, reserve1, reserve0)?;
+    let (amount_a, amount_b) = if amount_b_optimal <= max_amount_b {
+        (max_amount_a, amount_b_optimal)
+    } else {
+        (mul_div(max_amount_b, reserve0, reserve1)?, max_amount_b)
+    };
+
+    let liquidity = std::cmp::min(
+        mul_div(amount_a, supply, reserve0)?,
+        mul_div(amount_b, supply, reserve1)?,
+    );
+
+    if amount_a == 0 || amount_b == 0 || liquidity == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    Ok((amount_a, amount_b, liquidity))
+}
+
+// quote_deposit for any curve: the first deposit gets the invariant of the
+// pool's curve (D for StableSwap, a^w0 * b^w1 for weighted) instead of sqrt(a*b).
+// Later deposits are proportional to the reserves whatever the curve.
+fn quote_pool_deposit(
+    pool: &Pool,
+    max_amount_a: u64,
+    max_amount_b: u64,
+    reserve0: u64,
+    reserve1: u64,
+    supply: u64,
+    timestamp: i64,
+) -> Result<(u64, u64, u64)> {
+    if supply <= MINIMUM_LIQUIDITY {
+        let invariant = get_initial_liquidity(pool, max_amount_a, max_amount_b, timestamp)?;
+        return quote_first_deposit(max_amount_a, max_amount_b, invariant);
+    }
+
+    quote_deposit(max_amount_a, max_amount_b, reserve0, reserve1, supply)
+}
+
+fn quote_first_deposit(amount_a: u64, amount_b: u64, invariant: u64) -> Result<(u64, u64, u64)> {
+    let liquidity = invariant
+        .checked_sub(MINIMUM_LIQUIDITY)
+        .filter(|liquidity| *liquidity > 0)
+        .ok_or(CustomError::InvalidLiquidity)?;
+
+    Ok((amount_a, amount_b, liquidity))
+}
+
+pub fn deposit(
+    mut ctx: Context<DepositLiquidity>,
+    max_amount_a: u64,
+    max_amount_b: u64,
+    min_liquidity_out: u64,
+) -> Result<()> {
     // token0 transfer
 
     let decimals0 = ctx.accounts.token0.decimals;
     let decimals1 = ctx.accounts.token1.decimals;
 
-    if decimals0 != decimals1 {
-        return err!(CustomError::DecimalsNotEqual);
-    }
-
-    if amount_a == 0 || amount_b == 0 {
+    if max_amount_a == 0 || max_amount_b == 0 {
         return err!(CustomError::ZeroAmount);
     }
 
-    let vault_a = &ctx.accounts.token0_vault;
-    let vault_b = &ctx.accounts.token1_vault;
+    let token0_info = ctx.accounts.token0.to_account_info();
+    let token1_info = ctx.accounts.token1.to_account_info();
 
-    let pool_creation = vault_a.amount == 0 && vault_b.amount == 0;
+    // LP is priced on what reaches the vaults after Token-2022 transfer fees,
+    // then the amounts to send are grossed back up
+    let (reserve0, reserve1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    ctx.accounts.pool.update_price_accumulators(timestamp);
+    ctx.accounts
+        .observations
+        .write(&ctx.accounts.pool, timestamp);
+    let (amount_a_received, amount_b_received, liquidity) = quote_pool_deposit(
+        &ctx.accounts.pool,
+        max_amount_a - get_transfer_fee(&token0_info, max_amount_a)?,
+        max_amount_b - get_transfer_fee(&token1_info, max_amount_b)?,
+        reserve0,
+        reserve1,
+        ctx.accounts.tokenliq.supply,
+        timestamp,
+    )?;
+    let amount_a = get_pre_fee_amount(&token0_info, amount_a_received)?.min(max_amount_a);
+    let amount_b = get_pre_fee_amount(&token1_info, amount_b_received)?.min(max_amount_b);
 
-    (amount_a, amount_b) = if pool_creation {
-        (amount_a, amount_b)
-    } else {
-        let ratio = I64F64::from_num(vault_a.amount)
-            .checked_mul(I64F64::from_num(vault_b.amount))
-            .unwrap();
-        if vault_a.amount > vault_b.amount {
-            (
-                I64F64::from_num(amount_b)
-                    .checked_mul(ratio)
-                    .unwrap()
-                    .to_num::<u64>(),
-                amount_b,
-            )
-        } else {
-            (
-                amount_a,
-                I64F64::from_num(amount_a)
-                    .checked_div(ratio)
-                    .unwrap()
-                    .to_num::<u64>(),
-            )
-        }
-    };
+    if liquidity < min_liquidity_out {
+        return err!(CustomError::InsufficientLiquidityOut);
+    }
 
     // write tests for insufficient funds
     // transfering token0
     token_interface::transfer_checked(
         CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.token0_program.to_account_info(),
             TransferChecked {
                 mint: ctx.accounts.token0.to_account_info(),
                 from: ctx.accounts.depositor_account_0.to_account_info(),
@@ -75,7 +145,7 @@ pub fn deposit(ctx: Context<DepositLiquidity>, mut amount_a: u64, mut amount_b:
 
     token_interface::transfer_checked(
         CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.token1_program.to_account_info(),
             TransferChecked {
                 mint: ctx.accounts.token1.to_account_info(),
                 from: ctx.accounts.depositor_account_1.to_account_info(),
@@ -87,16 +157,6 @@ pub fn deposit(ctx: Context<DepositLiquidity>, mut amount_a: u64, mut amount_b:
         decimals1,
     )?;
 
-    // calc liquidity
-    // sqrt(a*b)
-
-    let liquidity = I64F64::from_num(amount_a)
-        .checked_mul(I64F64::from_num(amount_b))
-        .ok_or(CustomError::MathOverflow)?
-        .sqrt()
-        .checked_to_num::<u64>()
-        .ok_or(CustomError::InvalidLiquidity)?;
-
     let pool_key = ctx.accounts.pool.key();
     let token0_key = ctx.accounts.token0.key();
     let token1_key = ctx.accounts.token1.key();
@@ -124,23 +184,46 @@ pub fn deposit(ctx: Context<DepositLiquidity>, mut amount_a: u64, mut amount_b:
         liquidity,
     )?;
 
-    let mut pool = &mut ctx.accounts.pool;
+    let pool = &mut ctx.accounts.pool;
     pool.liquidity += liquidity;
 
+    let (reserve0, reserve1) = get_post_reserves(&mut ctx)?;
+    emit!(LiquidityAdded {
+        pool: pool_key,
+        depositor: ctx.accounts.signer.key(),
+        amount0: amount_a,
+        amount1: amount_b,
+        liquidity_minted: liquidity,
+        reserve0,
+        reserve1,
+    });
+
     Ok(())
 }
 
+fn get_post_reserves(ctx: &mut Context<DepositLiquidity>) -> Result<(u64, u64)> {
+    ctx.accounts.token0_vault.reload()?;
+    ctx.accounts.token1_vault.reload()?;
+    ctx.accounts.pool.sync_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )
+}
+
 #[derive(Accounts)]
 pub struct DepositLiquidity<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    #[account(mint::token_program = token0_program)]
     pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
     pub token1: InterfaceAccount<'info, Mint>,
     #[account(
         mut,
         seeds = [b"tokenliq", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
         bump,
+        mint::token_program = token_program,
     )]
     pub tokenliq: InterfaceAccount<'info, Mint>,
 
@@ -148,6 +231,7 @@ pub struct DepositLiquidity<'info> {
         mut,
         associated_token::mint = token0,
         associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
     )]
     pub token0_vault: InterfaceAccount<'info, TokenAccount>,
 
@@ -155,6 +239,7 @@ pub struct DepositLiquidity<'info> {
         mut,
         associated_token::mint = token1,
         associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
     )]
     pub token1_vault: InterfaceAccount<'info, TokenAccount>,
 
@@ -162,6 +247,7 @@ pub struct DepositLiquidity<'info> {
         mut,
         associated_token::mint = token0,
         associated_token::authority = signer,
+        associated_token::token_program = token0_program,
     )]
     pub depositor_account_0: InterfaceAccount<'info, TokenAccount>,
 
@@ -169,6 +255,7 @@ pub struct DepositLiquidity<'info> {
         mut,
         associated_token::mint = token1,
         associated_token::authority = signer,
+        associated_token::token_program = token1_program,
     )]
     pub depositor_account_1: InterfaceAccount<'info, TokenAccount>,
 
@@ -176,18 +263,40 @@ pub struct DepositLiquidity<'info> {
         mut,
         associated_token::mint = tokenliq,
         associated_token::authority = signer,
+        associated_token::token_program = token_program,
     )]
     pub depositor_account_liq: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        seeds = [b"pool", token0.key().as_ref(), token1.key().as_ref()],
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
         bump,
         has_one = token0,
-        has_one = token1
+        has_one = token1,
+        constraint = pool.status == PoolStatus::Active @ CustomError::PoolPaused,
+        constraint = !pool.flash_loan_active @ CustomError::FlashLoanActive,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump
+    )]
+    pub observations: Account<'info, Observations>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = !admin_settings.paused @ CustomError::ProtocolPaused,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
     /// CHECK: readolny
     #[account(
         seeds = [b"pool_authority", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
@@ -195,7 +304,9 @@ pub struct DepositLiquidity<'info> {
     )]
     pub pool_authority: AccountInfo<'info>,
 
-    pub token_program: Program<'info, Token>,
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
@@ -0,0 +1,302 @@
+use {
+    anchor_lang::{
+        prelude::*,
+        solana_program::sysvar::instructions::{
+            self, load_current_index_checked, load_instruction_at_checked,
+        },
+        Discriminator,
+    },
+    anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::error::*;
+use crate::events::*;
+use crate::math::*;
+use crate::state::*;
+
+// Position of `pool` in FlashLoan, used to match the repay instruction
+const POOL_ACCOUNT_INDEX: usize = 3;
+
+// Charged on each borrowed amount at the pool's swap fee rate, rounded up.
+// It is paid back into the vaults without a protocol share, so it all goes to LPs.
+pub fn get_flash_loan_fee(amount: u64, fee_bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(CustomError::MathOverflow)?
+        .div_ceil(FEE_DENOMINATOR as u128);
+
+    u64::try_from(fee).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// Lends `amount0` / `amount1` from the vaults. Only allowed when a flash_repay
+// for the same pool follows later in the transaction; that instruction pulls
+// the loan plus the fee back, so the transaction fails unless it is repaid.
+pub fn flash_borrow(ctx: Context<FlashLoan>, amount0: u64, amount1: u64) -> Result<()> {
+    if amount0 == 0 && amount1 == 0 {
+        return err!(CustomError::ZeroAmount);
+    }
+    if ctx.accounts.pool.flash_loan_active {
+        return err!(CustomError::FlashLoanActive);
+    }
+
+    let (reserve0, reserve1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+    if amount0 > reserve0 || amount1 > reserve1 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    check_repaid_later(&ctx)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.flash_loan_active = true;
+    pool.flash_loan_amount0 = amount0;
+    pool.flash_loan_amount1 = amount1;
+
+    let pool_key = ctx.accounts.pool.key();
+    let token0_key = ctx.accounts.token0.key();
+    let token1_key = ctx.accounts.token1.key();
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        "pool_authority".as_bytes(),
+        pool_key.as_ref(),
+        token0_key.as_ref(),
+        token1_key.as_ref(),
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let accounts = &ctx.accounts;
+    let legs = [
+        (
+            amount0,
+            &accounts.token0,
+            &accounts.token0_vault,
+            &accounts.borrower_account_0,
+            &accounts.token0_program,
+        ),
+        (
+            amount1,
+            &accounts.token1,
+            &accounts.token1_vault,
+            &accounts.borrower_account_1,
+            &accounts.token1_program,
+        ),
+    ];
+    for (amount, mint, vault, borrower_account, token_program) in legs {
+        if amount == 0 {
+            continue;
+        }
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    mint: mint.to_account_info(),
+                    from: vault.to_account_info(),
+                    to: borrower_account.to_account_info(),
+                    authority: accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+    }
+
+    Ok(())
+}
+
+// Pays back the open loan plus its fee. The transfers are grossed up for
+// Token-2022 transfer fees so the vaults receive the full amount owed.
+pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    if !pool.flash_loan_active {
+        return err!(CustomError::FlashLoanNotActive);
+    }
+
+    let amount0 = pool.flash_loan_amount0;
+    let amount1 = pool.flash_loan_amount1;
+    let fee0 = get_flash_loan_fee(amount0, pool.fee_bps)?;
+    let fee1 = get_flash_loan_fee(amount1, pool.fee_bps)?;
+
+    let accounts = &ctx.accounts;
+    let legs = [
+        (
+            amount0,
+            fee0,
+            &accounts.token0,
+            &accounts.token0_vault,
+            &accounts.borrower_account_0,
+            &accounts.token0_program,
+        ),
+        (
+            amount1,
+            fee1,
+            &accounts.token1,
+            &accounts.token1_vault,
+            &accounts.borrower_account_1,
+            &accounts.token1_program,
+        ),
+    ];
+    for (amount, fee, mint, vault, borrower_account, token_program) in legs {
+        if amount == 0 {
+            continue;
+        }
+
+        let amount_owed = amount.checked_add(fee).ok_or(CustomError::MathOverflow)?;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                TransferChecked {
+                    mint: mint.to_account_info(),
+                    from: borrower_account.to_account_info(),
+                    to: vault.to_account_info(),
+                    authority: accounts.borrower.to_account_info(),
+                },
+            ),
+            get_pre_fee_amount(&mint.to_account_info(), amount_owed)?,
+            mint.decimals,
+        )?;
+    }
+
+    // the fee grows the reserves; the price up to now is the one from before
+    // the loan, which left the stored reserves alone
+    ctx.accounts
+        .pool
+        .update_price_accumulators(Clock::get()?.unix_timestamp);
+    ctx.accounts.token0_vault.reload()?;
+    ctx.accounts.token1_vault.reload()?;
+    ctx.accounts.pool.sync_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.flash_loan_active = false;
+    pool.flash_loan_amount0 = 0;
+    pool.flash_loan_amount1 = 0;
+
+    emit!(FlashLoanRepaid {
+        pool: ctx.accounts.pool.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount0,
+        amount1,
+        fee0,
+        fee1,
+    });
+
+    Ok(())
+}
+
+// Looks for a top-level flash_repay on the same pool after the current
+// instruction. flash_borrow refuses to open a second loan on the pool, so the
+// first one found has to be the repay of this loan.
+fn check_repaid_later(ctx: &Context<FlashLoan>) -> Result<()> {
+    let instructions = &ctx.accounts.instructions;
+    let current_index = load_current_index_checked(instructions)? as usize;
+
+    for index in current_index + 1.. {
+        let instruction = match load_instruction_at_checked(index, instructions) {
+            Ok(instruction) => instruction,
+            // past the last instruction of the transaction
+            Err(ProgramError::InvalidArgument) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        if instruction.program_id == crate::ID
+            && instruction
+                .data
+                .starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+            && instruction
+                .accounts
+                .get(POOL_ACCOUNT_INDEX)
+                .is_some_and(|meta| meta.pubkey == ctx.accounts.pool.key())
+        {
+            return Ok(());
+        }
+    }
+
+    err!(CustomError::FlashLoanNotRepaid)
+}
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    #[account(mint::token_program = token0_program)]
+    pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
+    pub token1: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
+        bump,
+        has_one = token0,
+        has_one = token1,
+        constraint = pool.status == PoolStatus::Active @ CustomError::PoolPaused,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = !admin_settings.paused @ CustomError::ProtocolPaused,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    /// CHECK: readonly
+    #[account(
+        seeds = [b"pool_authority", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token0,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
+    )]
+    pub token0_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token1,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
+    )]
+    pub token1_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token0,
+        token::authority = borrower,
+        token::token_program = token0_program,
+    )]
+    pub borrower_account_0: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token1,
+        token::authority = borrower,
+        token::token_program = token1_program,
+    )]
+    pub borrower_account_1: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: the instructions sysvar, read by flash_borrow
+    #[account(address = instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+}
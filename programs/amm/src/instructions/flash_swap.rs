@@ -0,0 +1,273 @@
+use {
+    anchor_lang::{
+        prelude::*,
+        solana_program::{
+            instruction::{AccountMeta, Instruction},
+            program::invoke,
+        },
+    },
+    anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::curve::*;
+use crate::error::*;
+use crate::events::*;
+use crate::instructions::get_swap_fees;
+use crate::state::*;
+
+// Uniswap V2 style: sends `amount0_out` / `amount1_out` to the recipients
+// first, then invokes `callback_program` with `callback_data` as instruction
+// data and the remaining accounts as its accounts. Whatever the callback has
+// paid into the vaults by the time it returns counts as input, and the
+// fee-adjusted invariant of the curve has to hold on the vault balances.
+pub fn flash_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlashSwap<'info>>,
+    amount0_out: u64,
+    amount1_out: u64,
+    callback_data: Vec<u8>,
+) -> Result<()> {
+    if amount0_out == 0 && amount1_out == 0 {
+        return err!(CustomError::ZeroAmount);
+    }
+
+    let (reserve0, reserve1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+    if amount0_out >= reserve0 || amount1_out >= reserve1 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    ctx.accounts.pool.update_price_accumulators(timestamp);
+    ctx.accounts
+        .observations
+        .write(&ctx.accounts.pool, timestamp);
+
+    // persisted before the callback so anything it calls back into sees the
+    // pool locked
+    ctx.accounts.pool.flash_loan_active = true;
+    ctx.accounts.pool.exit(&crate::ID)?;
+
+    transfer_flash_swap_amounts(&ctx, amount0_out, amount1_out)?;
+
+    let callback = Instruction {
+        program_id: ctx.accounts.callback_program.key(),
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: callback_data,
+    };
+    let mut callback_accounts = ctx.remaining_accounts.to_vec();
+    callback_accounts.push(ctx.accounts.callback_program.to_account_info());
+    invoke(&callback, &callback_accounts)?;
+
+    ctx.accounts.token0_vault.reload()?;
+    ctx.accounts.token1_vault.reload()?;
+    let (balance0, balance1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+    let amount0_in = balance0.saturating_sub(reserve0 - amount0_out);
+    let amount1_in = balance1.saturating_sub(reserve1 - amount1_out);
+
+    // fee-adjusted invariant of the pool's curve, x * y >= k for constant product
+    check_invariant(
+        &ctx.accounts.pool,
+        (reserve0, reserve1),
+        (balance0, balance1),
+        (amount0_in, amount1_in),
+        timestamp,
+    )?;
+
+    let protocol_fee_bps = ctx.accounts.admin_settings.protocol_fee_bps;
+    let (_, protocol_fee0) =
+        get_swap_fees(amount0_in, ctx.accounts.pool.fee_bps, protocol_fee_bps)?;
+    let (_, protocol_fee1) =
+        get_swap_fees(amount1_in, ctx.accounts.pool.fee_bps, protocol_fee_bps)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.protocol_fees_token0 = pool
+        .protocol_fees_token0
+        .checked_add(protocol_fee0)
+        .ok_or(CustomError::MathOverflow)?;
+    pool.protocol_fees_token1 = pool
+        .protocol_fees_token1
+        .checked_add(protocol_fee1)
+        .ok_or(CustomError::MathOverflow)?;
+    pool.flash_loan_active = false;
+    let (reserve0, reserve1) = pool.sync_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+
+    emit!(FlashSwapped {
+        pool: ctx.accounts.pool.key(),
+        swapper: ctx.accounts.swapper.key(),
+        amount0_out,
+        amount1_out,
+        amount0_in,
+        amount1_in,
+        protocol_fee0,
+        protocol_fee1,
+        reserve0,
+        reserve1,
+    });
+
+    Ok(())
+}
+
+fn transfer_flash_swap_amounts(
+    ctx: &Context<FlashSwap>,
+    amount0_out: u64,
+    amount1_out: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let pool_key = accounts.pool.key();
+    let token0_key = accounts.token0.key();
+    let token1_key = accounts.token1.key();
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        "pool_authority".as_bytes(),
+        pool_key.as_ref(),
+        token0_key.as_ref(),
+        token1_key.as_ref(),
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    let legs = [
+        (
+            amount0_out,
+            &accounts.token0,
+            &accounts.token0_vault,
+            &accounts.recipient_account_0,
+            &accounts.token0_program,
+        ),
+        (
+            amount1_out,
+            &accounts.token1,
+            &accounts.token1_vault,
+            &accounts.recipient_account_1,
+            &accounts.token1_program,
+        ),
+    ];
+    for (amount, mint, vault, recipient_account, token_program) in legs {
+        if amount == 0 {
+            continue;
+        }
+
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TransferChecked {
+                    mint: mint.to_account_info(),
+                    from: vault.to_account_info(),
+                    to: recipient_account.to_account_info(),
+                    authority: accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            mint.decimals,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FlashSwap<'info> {
+    #[account(mut)]
+    pub swapper: Signer<'info>,
+
+    #[account(mint::token_program = token0_program)]
+    pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
+    pub token1: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
+        bump,
+        has_one = token0,
+        has_one = token1,
+        constraint = pool.status == PoolStatus::Active @ CustomError::PoolPaused,
+        constraint = !pool.flash_loan_active @ CustomError::FlashLoanActive,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump
+    )]
+    pub observations: Account<'info, Observations>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = !admin_settings.paused @ CustomError::ProtocolPaused,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    /// CHECK: readonly
+    #[account(
+        seeds = [b"pool_authority", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
+        bump
+    )]
+    pub pool_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token0,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
+    )]
+    pub token0_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = token1,
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
+    )]
+    pub token1_vault: InterfaceAccount<'info, TokenAccount>,
+
+    // receive the amounts out, usually accounts of the callback program
+    #[account(
+        mut,
+        token::mint = token0,
+        token::token_program = token0_program,
+    )]
+    pub recipient_account_0: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token1,
+        token::token_program = token1_program,
+    )]
+    pub recipient_account_1: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: any program but this one; it only gets the signatures of the transaction
+    #[account(
+        executable,
+        constraint = callback_program.key() != crate::ID @ CustomError::InvalidCallbackProgram,
+    )]
+    pub callback_program: AccountInfo<'info>,
+
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+}
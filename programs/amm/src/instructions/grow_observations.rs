@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::state::*;
+
+// Permissionless: whoever wants a longer price history pays the rent for it.
+// Each call can add at most 10KB, about 179 observations.
+pub fn grow_observations(ctx: Context<ObservationsGrow>, new_capacity: u16) -> Result<()> {
+    let observations = &mut ctx.accounts.observations.observations;
+    if new_capacity as usize <= observations.len() || new_capacity > Observations::MAX_CAPACITY {
+        return err!(CustomError::InvalidObservationCapacity);
+    }
+
+    observations.resize(new_capacity as usize, Observation::default());
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(new_capacity: u16)]
+pub struct ObservationsGrow<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump,
+        realloc = 8 + Observations::space(new_capacity as usize),
+        realloc::payer = payer,
+        realloc::zero = false,
+    )]
+    pub observations: Account<'info, Observations>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -1,15 +1,26 @@
 use anchor_lang::prelude::*;
 
 use crate::error::*;
+use crate::events::*;
+use crate::program::Amm;
 use crate::state::*;
 
 pub fn init_admin(ctx: Context<InitAdmin>, new_admin: Pubkey) -> Result<()> {
     let admin_settings_account = &mut ctx.accounts.admin_settings;
     admin_settings_account.admin = new_admin;
+    admin_settings_account.treasury = new_admin;
+
+    // the first admin starts out holding every role
+    let admin_role = &mut ctx.accounts.admin_role;
+    admin_role.holder = new_admin;
+    admin_role.roles = RoleAssignment::ALL;
+
+    emit!(AdminInitialized { admin: new_admin });
     Ok(())
 }
 
 #[derive(Accounts)]
+#[instruction(new_admin: Pubkey)]
 pub struct InitAdmin<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
@@ -17,11 +28,31 @@ pub struct InitAdmin<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 32,
+        space = 8 + AdminSettings::INIT_SPACE,
         seeds = [b"admin"],
         bump
     )]
     pub admin_settings: Account<'info, AdminSettings>,
 
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RoleAssignment::INIT_SPACE,
+        seeds = [b"role", new_admin.as_ref()],
+        bump
+    )]
+    pub admin_role: Account<'info, RoleAssignment>,
+
+    // Only the upgrade authority may claim the admin PDA, so nobody can
+    // front-run it right after deployment
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, Amm>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ CustomError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
     pub system_program: Program<'info, System>,
 }
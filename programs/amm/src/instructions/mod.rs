@@ -15,3 +15,39 @@ pub use withdraw::*;
 
 mod swap;
 pub use swap::*;
+
+mod allow_mint;
+pub use allow_mint::*;
+
+mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+mod set_pool_status;
+pub use set_pool_status::*;
+
+mod protocol_settings;
+pub use protocol_settings::*;
+
+mod roles;
+pub use roles::*;
+
+mod amm_config;
+pub use amm_config::*;
+
+mod observe;
+pub use observe::*;
+
+mod grow_observations;
+pub use grow_observations::*;
+
+mod swap_route;
+pub use swap_route::*;
+
+mod flash_loan;
+pub use flash_loan::*;
+
+mod flash_swap;
+pub use flash_swap::*;
+
+mod ramp_amp;
+pub use ramp_amp::*;
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::error::*;
+use crate::state::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceObservation {
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    pub timestamp: i64,
+}
+
+// Read-only: returns the accumulators brought forward to the current time.
+// A TWAP over a window is the difference of two observations divided by the
+// seconds between them, with wrapping subtraction.
+pub fn observe(ctx: Context<Observe>) -> Result<PriceObservation> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    let (price0_cumulative, price1_cumulative) = ctx.accounts.pool.get_cumulative_prices(timestamp);
+
+    Ok(PriceObservation {
+        price0_cumulative,
+        price1_cumulative,
+        timestamp,
+    })
+}
+
+// Time-weighted averages over a window; prices are Q64.64
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct Twap {
+    pub price0: u128,
+    pub price1: u128,
+    pub liquidity: u128,
+}
+
+// Read-only: TWAP between two timestamps, each no older than the oldest
+// observation and no later than now
+pub fn get_twap(ctx: Context<Observe>, start_timestamp: i64, end_timestamp: i64) -> Result<Twap> {
+    if start_timestamp >= end_timestamp || end_timestamp > Clock::get()?.unix_timestamp {
+        return err!(CustomError::InvalidTimeWindow);
+    }
+
+    let pool = &ctx.accounts.pool;
+    let observations = &ctx.accounts.observations;
+    let start = observations.observe_at(pool, start_timestamp)?;
+    let end = observations.observe_at(pool, end_timestamp)?;
+
+    let elapsed = (end_timestamp - start_timestamp) as u128;
+    Ok(Twap {
+        price0: end.price0_cumulative.wrapping_sub(start.price0_cumulative) / elapsed,
+        price1: end.price1_cumulative.wrapping_sub(start.price1_cumulative) / elapsed,
+        liquidity: end
+            .liquidity_cumulative
+            .wrapping_sub(start.liquidity_cumulative)
+            / elapsed,
+    })
+}
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    pub token0: InterfaceAccount<'info, Mint>,
+    pub token1: InterfaceAccount<'info, Mint>,
+
+    // priced from the reserves stored on the pool, so the vaults aren't read
+    #[account(
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
+        bump,
+        has_one = token0,
+        has_one = token1,
+    )]
+    pub pool: Account<'info, Pool>,
+
+    #[account(seeds = [b"observations", pool.key().as_ref()], bump)]
+    pub observations: Account<'info, Observations>,
+}
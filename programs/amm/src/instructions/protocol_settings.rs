@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::events::*;
+use crate::instructions::AdminSet;
+use crate::state::*;
+
+pub fn set_protocol_fee(ctx: Context<ProtocolFeeSet>, protocol_fee_bps: u16) -> Result<()> {
+    if protocol_fee_bps as u64 > FEE_DENOMINATOR {
+        return err!(CustomError::InvalidFee);
+    }
+
+    let admin_settings_account = &mut ctx.accounts.admin_settings;
+    admin_settings_account.protocol_fee_bps = protocol_fee_bps;
+
+    emit!(ProtocolFeeChanged {
+        fee_manager: ctx.accounts.fee_manager.key(),
+        protocol_fee_bps,
+    });
+    Ok(())
+}
+
+pub fn set_global_pause(ctx: Context<GlobalPauseSet>, paused: bool) -> Result<()> {
+    let admin_settings_account = &mut ctx.accounts.admin_settings;
+    admin_settings_account.paused = paused;
+
+    emit!(GlobalPauseChanged {
+        pauser: ctx.accounts.pauser.key(),
+        paused,
+    });
+    Ok(())
+}
+
+pub fn set_pool_creation_config(
+    ctx: Context<AdminSet>,
+    permissionless: bool,
+    creation_fee: u64,
+    treasury: Pubkey,
+) -> Result<()> {
+    let admin_settings_account = &mut ctx.accounts.admin_settings;
+    admin_settings_account.permissionless_pool_creation = permissionless;
+    admin_settings_account.pool_creation_fee = creation_fee;
+    admin_settings_account.treasury = treasury;
+
+    emit!(PoolCreationConfigChanged {
+        admin: admin_settings_account.admin,
+        permissionless,
+        creation_fee,
+        treasury,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ProtocolFeeSet<'info> {
+    pub fee_manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", fee_manager.key().as_ref()],
+        bump,
+        constraint = fee_manager_role.has(RoleAssignment::FEE_MANAGER) @ CustomError::MissingRole,
+    )]
+    pub fee_manager_role: Account<'info, RoleAssignment>,
+
+    #[account(mut, seeds = [b"admin"], bump)]
+    pub admin_settings: Account<'info, AdminSettings>,
+}
+
+#[derive(Accounts)]
+pub struct GlobalPauseSet<'info> {
+    pub pauser: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", pauser.key().as_ref()],
+        bump,
+        constraint = pauser_role.has(RoleAssignment::PAUSER) @ CustomError::MissingRole,
+    )]
+    pub pauser_role: Account<'info, RoleAssignment>,
+
+    #[account(mut, seeds = [b"admin"], bump)]
+    pub admin_settings: Account<'info, AdminSettings>,
+}
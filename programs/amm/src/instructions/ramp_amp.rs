@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::events::*;
+use crate::state::*;
+
+// Moves a StableSwap pool's amplification linearly from its current value to
+// `target_amp` by `ramp_end`. The ramp has to last at least a day and change
+// A by at most MAX_AMP_CHANGE either way, so LPs aren't exposed to a sudden
+// reshape of the curve. A new ramp replaces one still in progress.
+pub fn ramp_amp(ctx: Context<AmpRamp>, target_amp: u64, ramp_end: i64) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    if pool.curve_type != CurveType::StableSwap {
+        return err!(CustomError::NotStablePool);
+    }
+    if !(Pool::MIN_AMP..=Pool::MAX_AMP).contains(&target_amp) {
+        return err!(CustomError::InvalidAmp);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    let current_amp = pool.get_amp(timestamp);
+    if ramp_end < timestamp.saturating_add(Pool::MIN_AMP_RAMP_DURATION)
+        || target_amp > current_amp * Pool::MAX_AMP_CHANGE
+        || target_amp * Pool::MAX_AMP_CHANGE < current_amp
+    {
+        return err!(CustomError::InvalidAmpRamp);
+    }
+
+    pool.amp_initial = current_amp;
+    pool.amp_target = target_amp;
+    pool.amp_ramp_start = timestamp;
+    pool.amp_ramp_end = ramp_end;
+
+    emit!(AmpRampStarted {
+        pool: pool.key(),
+        amp_initial: current_amp,
+        amp_target: target_amp,
+        amp_ramp_start: timestamp,
+        amp_ramp_end: ramp_end,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AmpRamp<'info> {
+    pub fee_manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", fee_manager.key().as_ref()],
+        bump,
+        constraint = fee_manager_role.has(RoleAssignment::FEE_MANAGER) @ CustomError::MissingRole,
+    )]
+    pub fee_manager_role: Account<'info, RoleAssignment>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            pool.token0.as_ref(),
+            pool.token1.as_ref(),
+        ],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+}
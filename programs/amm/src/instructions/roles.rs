@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::events::*;
+use crate::state::*;
+
+pub fn grant_role(ctx: Context<RoleGrant>, holder: Pubkey, roles: u8) -> Result<()> {
+    if roles & !RoleAssignment::ALL != 0 {
+        return err!(CustomError::InvalidRole);
+    }
+
+    let role_assignment = &mut ctx.accounts.role_assignment;
+    role_assignment.holder = holder;
+    role_assignment.roles |= roles;
+
+    emit!(RoleGranted {
+        holder,
+        roles,
+        holder_roles: role_assignment.roles,
+    });
+    Ok(())
+}
+
+pub fn revoke_role(ctx: Context<RoleRevoke>, roles: u8) -> Result<()> {
+    let role_assignment = &mut ctx.accounts.role_assignment;
+    role_assignment.roles &= !roles;
+
+    emit!(RoleRevoked {
+        holder: role_assignment.holder,
+        roles,
+        holder_roles: role_assignment.roles,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(holder: Pubkey)]
+pub struct RoleGrant<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin @ CustomError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + RoleAssignment::INIT_SPACE,
+        seeds = [b"role", holder.as_ref()],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RoleRevoke<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin @ CustomError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(
+        mut,
+        seeds = [b"role", role_assignment.holder.as_ref()],
+        bump
+    )]
+    pub role_assignment: Account<'info, RoleAssignment>,
+}
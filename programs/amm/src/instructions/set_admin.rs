@@ -1,11 +1,53 @@
 use anchor_lang::prelude::*;
 
 use crate::error::*;
+use crate::events::*;
 use crate::state::*;
 
-pub fn set_admin(ctx: Context<AdminSet>, new_admin: Pubkey) -> Result<()> {
+// The admin only changes once the proposed key signs accept_admin, so a
+// mistyped key can't lock us out
+pub fn propose_admin(ctx: Context<AdminSet>, new_admin: Pubkey) -> Result<()> {
     let admin_settings_account = &mut ctx.accounts.admin_settings;
-    admin_settings_account.admin = new_admin;
+    admin_settings_account.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        admin: admin_settings_account.admin,
+        pending_admin: new_admin,
+    });
+    Ok(())
+}
+
+pub fn cancel_admin_proposal(ctx: Context<AdminSet>) -> Result<()> {
+    let admin_settings_account = &mut ctx.accounts.admin_settings;
+    let pending_admin = admin_settings_account.pending_admin;
+    admin_settings_account.pending_admin = Pubkey::default();
+
+    emit!(AdminProposalCancelled {
+        admin: admin_settings_account.admin,
+        pending_admin,
+    });
+    Ok(())
+}
+
+// The roles go with the admin key: the previous admin loses all of them and
+// the new one gets every role, as the first admin does in init_admin
+pub fn accept_admin(ctx: Context<AdminAccept>) -> Result<()> {
+    let admin_settings_account = &mut ctx.accounts.admin_settings;
+    let previous_admin = admin_settings_account.admin;
+    admin_settings_account.admin = ctx.accounts.pending_admin.key();
+    admin_settings_account.pending_admin = Pubkey::default();
+
+    // admin_role is written back after previous_admin_role, so a key
+    // accepting its own proposal keeps its roles
+    ctx.accounts.previous_admin_role.roles = 0;
+    let admin_role = &mut ctx.accounts.admin_role;
+    admin_role.holder = ctx.accounts.pending_admin.key();
+    admin_role.roles = RoleAssignment::ALL;
+
+    emit!(AdminAccepted {
+        previous_admin,
+        admin: admin_settings_account.admin,
+    });
     Ok(())
 }
 
@@ -20,3 +62,33 @@ pub struct AdminSet<'info> {
     )]
     pub admin_settings: Account<'info, AdminSettings>,
 }
+
+#[derive(Accounts)]
+pub struct AdminAccept<'info> {
+    #[account(mut)]
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = pending_admin @ CustomError::Unauthorized,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(
+        mut,
+        seeds = [b"role", admin_settings.admin.as_ref()],
+        bump
+    )]
+    pub previous_admin_role: Account<'info, RoleAssignment>,
+
+    #[account(
+        init_if_needed,
+        payer = pending_admin,
+        space = 8 + RoleAssignment::INIT_SPACE,
+        seeds = [b"role", pending_admin.key().as_ref()],
+        bump
+    )]
+    pub admin_role: Account<'info, RoleAssignment>,
+
+    pub system_program: Program<'info, System>,
+}
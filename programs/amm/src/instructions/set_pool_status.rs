@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::events::*;
+use crate::state::*;
+
+pub fn set_pool_status(ctx: Context<PoolStatusSet>, status: PoolStatus) -> Result<()> {
+    if status == PoolStatus::Delisted {
+        return err!(CustomError::Unauthorized);
+    }
+
+    let pool = &mut ctx.accounts.pool;
+    pool.status = status;
+
+    emit!(PoolStatusChanged {
+        pool: pool.key(),
+        authority: ctx.accounts.pauser.key(),
+        status,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PoolStatusSet<'info> {
+    pub pauser: Signer<'info>,
+
+    #[account(
+        seeds = [b"role", pauser.key().as_ref()],
+        bump,
+        constraint = pauser_role.has(RoleAssignment::PAUSER) @ CustomError::MissingRole,
+    )]
+    pub pauser_role: Account<'info, RoleAssignment>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            pool.token0.as_ref(),
+            pool.token1.as_ref(),
+        ],
+        bump,
+        constraint = pool.status != PoolStatus::Delisted @ CustomError::PoolDelisted,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+pub fn delist_pool(ctx: Context<PoolDelist>) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.status = PoolStatus::Delisted;
+
+    emit!(PoolStatusChanged {
+        pool: pool.key(),
+        authority: ctx.accounts.admin.key(),
+        status: PoolStatus::Delisted,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PoolDelist<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        has_one = admin @ CustomError::Unauthorized,
+        seeds = [b"admin"],
+        bump
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            pool.token0.as_ref(),
+            pool.token1.as_ref(),
+        ],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+}
@@ -1,140 +1,323 @@
 use {
     anchor_lang::prelude::*,
-    anchor_spl::{
-        associated_token::AssociatedToken,
-        token::Token,
-        token_interface::{self, Mint, TokenAccount, TransferChecked},
-    },
-    fixed::types::I64F64,
+    anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
+use crate::curve::*;
+use crate::error::*;
+use crate::events::*;
+use crate::math::*;
 use crate::state::*;
 
-pub fn swapExactInput(ctx: Context<SwapExactInput>, amount: u64) -> Result<()> {
-    let mut amount_to_transfer_to_user = 0;
+// x * y = k, with the fee taken from the input before pricing. The fee part
+// of the input stays in the vault and grows the value of every LP share.
+pub fn get_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+) -> Result<u64> {
+    let amount_in_with_fee = (amount_in as u128)
+        .checked_mul((FEE_DENOMINATOR - fee_bps as u64) as u128)
+        .ok_or(CustomError::MathOverflow)?;
+
+    let numerator = amount_in_with_fee
+        .checked_mul(reserve_out as u128)
+        .ok_or(CustomError::MathOverflow)?;
+    let denominator = (reserve_in as u128)
+        .checked_mul(FEE_DENOMINATOR as u128)
+        .ok_or(CustomError::MathOverflow)?
+        .checked_add(amount_in_with_fee)
+        .ok_or(CustomError::MathOverflow)?;
+
+    if denominator == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    u64::try_from(numerator / denominator).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// Inverse of get_amount_out: the smallest input that buys `amount_out`.
+// Rounded up so the pool never gives away more than it is paid for.
+pub fn get_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+) -> Result<u64> {
+    if amount_out >= reserve_out {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let numerator = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(CustomError::MathOverflow)?
+        .checked_mul(FEE_DENOMINATOR as u128)
+        .ok_or(CustomError::MathOverflow)?;
+    let denominator = ((reserve_out - amount_out) as u128)
+        .checked_mul((FEE_DENOMINATOR - fee_bps as u64) as u128)
+        .ok_or(CustomError::MathOverflow)?;
+
+    u64::try_from(numerator.div_ceil(denominator)).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+pub fn swapExactInput(
+    mut ctx: Context<SwapExactInput>,
+    amount: u64,
+    minimum_amount_out: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return err!(CustomError::ZeroAmount);
+    }
+
+    update_oracle(&mut ctx)?;
+    let (reserve_in, reserve_out) = get_reserves(&ctx)?;
+    let (mint_in, mint_out) = get_mints(&ctx);
+
+    // price on what the vault actually receives after Token-2022 transfer fees
+    let amount_in_received = amount - get_transfer_fee(&mint_in, amount)?;
+    // floor, so rounding always favours the pool
+    let amount_out = quote_amount_out(
+        &ctx.accounts.pool,
+        amount_in_received,
+        reserve_in,
+        reserve_out,
+        ctx.accounts.token_in.key() == ctx.accounts.token0.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    let amount_out_received = amount_out - get_transfer_fee(&mint_out, amount_out)?;
+
+    if amount_out_received < minimum_amount_out {
+        return err!(CustomError::SlippageExceeded);
+    }
+
+    transfer_swap_amounts(&ctx, amount, amount_out)?;
+    let (swap_fee, protocol_fee) = accrue_protocol_fee(&mut ctx, amount_in_received)?;
+    emit_swapped(
+        &mut ctx,
+        amount,
+        amount_out,
+        amount_out_received,
+        swap_fee,
+        protocol_fee,
+    )
+}
+
+pub fn swap_exact_output(
+    mut ctx: Context<SwapExactInput>,
+    amount_out: u64,
+    maximum_amount_in: u64,
+) -> Result<()> {
+    if amount_out == 0 {
+        return err!(CustomError::ZeroAmount);
+    }
+
+    update_oracle(&mut ctx)?;
+    let (reserve_in, reserve_out) = get_reserves(&ctx)?;
+    let (mint_in, mint_out) = get_mints(&ctx);
+
+    // `amount_out` is what the swapper receives, so both legs are grossed up
+    // by their Token-2022 transfer fees
+    let amount_out_sent = get_pre_fee_amount(&mint_out, amount_out)?;
+    let amount_in_received = quote_amount_in(
+        &ctx.accounts.pool,
+        amount_out_sent,
+        reserve_in,
+        reserve_out,
+        ctx.accounts.token_in.key() == ctx.accounts.token0.key(),
+        Clock::get()?.unix_timestamp,
+    )?;
+    let amount_in = get_pre_fee_amount(&mint_in, amount_in_received)?;
+
+    if amount_in > maximum_amount_in {
+        return err!(CustomError::SlippageExceeded);
+    }
+
+    transfer_swap_amounts(&ctx, amount_in, amount_out_sent)?;
+    let (swap_fee, protocol_fee) = accrue_protocol_fee(&mut ctx, amount_in_received)?;
+    emit_swapped(
+        &mut ctx,
+        amount_in,
+        amount_out_sent,
+        amount_out,
+        swap_fee,
+        protocol_fee,
+    )
+}
+
+// (reserve_in, reserve_out) as seen from the token_in side
+fn get_reserves(ctx: &Context<SwapExactInput>) -> Result<(u64, u64)> {
+    let (liquidity_token0, liquidity_token1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
 
     if ctx.accounts.token_in.key() == ctx.accounts.token0.key() {
-        let liquidity_token0 = ctx.accounts.token0_vault.amount;
-        let liquidity_token1 = ctx.accounts.token1_vault.amount;
-        // floor or ceil? i think rather ceil
-
-        amount_to_transfer_to_user = I64F64::from_num(amount)
-            .checked_mul(I64F64::from_num(liquidity_token1))
-            .unwrap()
-            .checked_div(
-                I64F64::from_num(liquidity_token0)
-                    .checked_add(I64F64::from_num(amount))
-                    .unwrap(),
-            )
-            .unwrap()
-            .to_num::<u64>();
-
-        token_interface::transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    mint: ctx.accounts.token0.to_account_info(),
-                    from: ctx.accounts.depositor_account_0.to_account_info(),
-                    to: ctx.accounts.token0_vault.to_account_info(),
-                    authority: ctx.accounts.swapper.to_account_info(),
-                },
-            ),
-            amount,
-            ctx.accounts.token0.decimals,
-        )?;
-
-        let pool_key = ctx.accounts.pool.key();
-        let token0_key = ctx.accounts.token0.key();
-        let token1_key = ctx.accounts.token1.key();
-
-        let authority_bump = ctx.bumps.pool_authority;
-        let authority_seeds = &[
-            "pool_authority".as_bytes(),
-            pool_key.as_ref(),
-            token0_key.as_ref(),
-            token1_key.as_ref(),
-            &[authority_bump],
-        ];
-        let signer_seeds = &[&authority_seeds[..]];
-
-        token_interface::transfer_checked(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    mint: ctx.accounts.token1.to_account_info(),
-                    from: ctx.accounts.token1_vault.to_account_info(),
-                    to: ctx.accounts.depositor_account_1.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_to_transfer_to_user,
-            ctx.accounts.token1.decimals,
-        )?;
-
-        return Ok(());
+        Ok((liquidity_token0, liquidity_token1))
+    } else {
+        Ok((liquidity_token1, liquidity_token0))
     }
+}
 
-    if ctx.accounts.token_in.key() == ctx.accounts.token1.key() {
-        let liquidity_token0 = ctx.accounts.token0_vault.amount;
-        let liquidity_token1 = ctx.accounts.token1_vault.amount;
-
-        amount_to_transfer_to_user = I64F64::from_num(amount)
-            .checked_mul(I64F64::from_num(liquidity_token0))
-            .unwrap()
-            .checked_div(
-                I64F64::from_num(liquidity_token1)
-                    .checked_add(I64F64::from_num(amount))
-                    .unwrap(),
-            )
-            .unwrap()
-            .to_num::<u64>();
-
-        token_interface::transfer_checked(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    mint: ctx.accounts.token1.to_account_info(),
-                    from: ctx.accounts.depositor_account_1.to_account_info(),
-                    to: ctx.accounts.token1_vault.to_account_info(),
-                    authority: ctx.accounts.swapper.to_account_info(),
-                },
-            ),
-            amount,
-            ctx.accounts.token1.decimals,
-        )?;
-
-        let pool_key = ctx.accounts.pool.key();
-        let token0_key = ctx.accounts.token0.key();
-        let token1_key = ctx.accounts.token1.key();
-
-        let authority_bump = ctx.bumps.pool_authority;
-        let authority_seeds = &[
-            "pool_authority".as_bytes(),
-            pool_key.as_ref(),
-            token0_key.as_ref(),
-            token1_key.as_ref(),
-            &[authority_bump],
-        ];
-        let signer_seeds = &[&authority_seeds[..]];
-
-        token_interface::transfer_checked(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                TransferChecked {
-                    mint: ctx.accounts.token0.to_account_info(),
-                    from: ctx.accounts.token0_vault.to_account_info(),
-                    to: ctx.accounts.depositor_account_0.to_account_info(),
-                    authority: ctx.accounts.pool_authority.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_to_transfer_to_user,
-            ctx.accounts.token0.decimals,
-        )?;
-
-        return Ok(());
+// (swap_fee, protocol_fee) charged on `amount_in`, the protocol fee being
+// its share of the swap fee
+pub fn get_swap_fees(amount_in: u64, fee_bps: u16, protocol_fee_bps: u16) -> Result<(u64, u64)> {
+    let swap_fee = mul_div(amount_in, fee_bps as u64, FEE_DENOMINATOR)?;
+    let protocol_fee = mul_div(swap_fee, protocol_fee_bps as u64, FEE_DENOMINATOR)?;
+    Ok((swap_fee, protocol_fee))
+}
+
+// Records the pre-swap price in the pool accumulators and the observations
+fn update_oracle(ctx: &mut Context<SwapExactInput>) -> Result<()> {
+    let timestamp = Clock::get()?.unix_timestamp;
+    ctx.accounts.pool.update_price_accumulators(timestamp);
+    ctx.accounts
+        .observations
+        .write(&ctx.accounts.pool, timestamp);
+    Ok(())
+}
+
+// Sets aside the protocol's share of the swap fee charged on `amount_in`.
+// It stays in the input vault until collected but leaves the reserves.
+// Returns (swap_fee, protocol_fee).
+fn accrue_protocol_fee(ctx: &mut Context<SwapExactInput>, amount_in: u64) -> Result<(u64, u64)> {
+    let (swap_fee, protocol_fee) = get_swap_fees(
+        amount_in,
+        ctx.accounts.pool.fee_bps,
+        ctx.accounts.admin_settings.protocol_fee_bps,
+    )?;
+
+    let zero_for_one = ctx.accounts.token_in.key() == ctx.accounts.token0.key();
+    let pool = &mut ctx.accounts.pool;
+    let protocol_fees = if zero_for_one {
+        &mut pool.protocol_fees_token0
+    } else {
+        &mut pool.protocol_fees_token1
+    };
+    *protocol_fees = protocol_fees
+        .checked_add(protocol_fee)
+        .ok_or(CustomError::MathOverflow)?;
+
+    Ok((swap_fee, protocol_fee))
+}
+
+fn emit_swapped(
+    ctx: &mut Context<SwapExactInput>,
+    amount_in: u64,
+    amount_out: u64,
+    amount_out_received: u64,
+    swap_fee: u64,
+    protocol_fee: u64,
+) -> Result<()> {
+    ctx.accounts.token0_vault.reload()?;
+    ctx.accounts.token1_vault.reload()?;
+    let (reserve0, reserve1) = ctx.accounts.pool.sync_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+
+    emit!(Swapped {
+        pool: ctx.accounts.pool.key(),
+        swapper: ctx.accounts.swapper.key(),
+        token_in: ctx.accounts.token_in.key(),
+        amount_in,
+        amount_out,
+        amount_out_received,
+        swap_fee,
+        protocol_fee,
+        reserve0,
+        reserve1,
+    });
+
+    Ok(())
+}
+
+// (mint_in, mint_out) account infos, for reading transfer fee extensions
+fn get_mints<'info>(
+    ctx: &Context<SwapExactInput<'info>>,
+) -> (AccountInfo<'info>, AccountInfo<'info>) {
+    let token0 = ctx.accounts.token0.to_account_info();
+    let token1 = ctx.accounts.token1.to_account_info();
+
+    if ctx.accounts.token_in.key() == ctx.accounts.token0.key() {
+        (token0, token1)
+    } else {
+        (token1, token0)
     }
+}
+
+// Pulls `amount_in` of token_in from the swapper and pays `amount_out` of the other token
+fn transfer_swap_amounts(
+    ctx: &Context<SwapExactInput>,
+    amount_in: u64,
+    amount_out: u64,
+) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let zero_for_one = accounts.token_in.key() == accounts.token0.key();
+
+    let (mint_in, mint_out) = if zero_for_one {
+        (&accounts.token0, &accounts.token1)
+    } else {
+        (&accounts.token1, &accounts.token0)
+    };
+    let (program_in, program_out) = if zero_for_one {
+        (&accounts.token0_program, &accounts.token1_program)
+    } else {
+        (&accounts.token1_program, &accounts.token0_program)
+    };
+    let (vault_in, vault_out) = if zero_for_one {
+        (&accounts.token0_vault, &accounts.token1_vault)
+    } else {
+        (&accounts.token1_vault, &accounts.token0_vault)
+    };
+    let (user_in, user_out) = if zero_for_one {
+        (&accounts.depositor_account_0, &accounts.depositor_account_1)
+    } else {
+        (&accounts.depositor_account_1, &accounts.depositor_account_0)
+    };
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            program_in.to_account_info(),
+            TransferChecked {
+                mint: mint_in.to_account_info(),
+                from: user_in.to_account_info(),
+                to: vault_in.to_account_info(),
+                authority: accounts.swapper.to_account_info(),
+            },
+        ),
+        amount_in,
+        mint_in.decimals,
+    )?;
+
+    let pool_key = accounts.pool.key();
+    let token0_key = accounts.token0.key();
+    let token1_key = accounts.token1.key();
+
+    let authority_bump = ctx.bumps.pool_authority;
+    let authority_seeds = &[
+        "pool_authority".as_bytes(),
+        pool_key.as_ref(),
+        token0_key.as_ref(),
+        token1_key.as_ref(),
+        &[authority_bump],
+    ];
+    let signer_seeds = &[&authority_seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            program_out.to_account_info(),
+            TransferChecked {
+                mint: mint_out.to_account_info(),
+                from: vault_out.to_account_info(),
+                to: user_out.to_account_info(),
+                authority: accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount_out,
+        mint_out.decimals,
+    )?;
 
     Ok(())
 }
@@ -144,7 +327,9 @@ pub struct SwapExactInput<'info> {
     #[account(mut)]
     pub swapper: Signer<'info>,
 
+    #[account(mint::token_program = token0_program)]
     pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
     pub token1: InterfaceAccount<'info, Mint>,
 
     #[account(
@@ -153,13 +338,35 @@ pub struct SwapExactInput<'info> {
     pub token_in: InterfaceAccount<'info, Mint>,
 
     #[account(
-        seeds = [b"pool", token0.key().as_ref(), token1.key().as_ref()],
+        mut,
+        seeds = [
+            b"pool",
+            pool.amm_config.as_ref(),
+            token0.key().as_ref(),
+            token1.key().as_ref(),
+        ],
         bump,
         has_one = token0,
-        has_one = token1
+        has_one = token1,
+        constraint = pool.status == PoolStatus::Active @ CustomError::PoolPaused,
+        constraint = !pool.flash_loan_active @ CustomError::FlashLoanActive,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump
+    )]
+    pub observations: Account<'info, Observations>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = !admin_settings.paused @ CustomError::ProtocolPaused,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
     /// CHECK: readonly
     #[account(
         seeds = [b"pool_authority", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
@@ -170,30 +377,35 @@ pub struct SwapExactInput<'info> {
     #[account(
         mut,
         associated_token::mint = token0,
-        associated_token::authority = pool_authority
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
     )]
     pub token0_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token1,
-        associated_token::authority = pool_authority
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
     )]
     pub token1_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token0,
-        associated_token::authority = swapper
+        associated_token::authority = swapper,
+        associated_token::token_program = token0_program,
     )]
     pub depositor_account_0: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token1,
-        associated_token::authority = swapper
+        associated_token::authority = swapper,
+        associated_token::token_program = token1_program,
     )]
     pub depositor_account_1: InterfaceAccount<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
 }
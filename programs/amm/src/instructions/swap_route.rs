@@ -0,0 +1,328 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::curve::*;
+use crate::error::*;
+use crate::events::*;
+use crate::instructions::get_swap_fees;
+use crate::math::*;
+use crate::state::*;
+
+// Accounts each hop takes from remaining_accounts, in this order:
+// pool, observations, pool_authority, token0, token1, token0_vault,
+// token1_vault, token0_program, token1_program
+pub const ACCOUNTS_PER_HOP: usize = 9;
+
+struct Hop<'info> {
+    pool: Account<'info, Pool>,
+    // loaded one hop at a time, a full ring buffer is a big share of the heap
+    observations: &'info AccountInfo<'info>,
+    pool_authority: AccountInfo<'info>,
+    authority_bump: u8,
+    token0: InterfaceAccount<'info, Mint>,
+    token1: InterfaceAccount<'info, Mint>,
+    token0_vault: InterfaceAccount<'info, TokenAccount>,
+    token1_vault: InterfaceAccount<'info, TokenAccount>,
+    token0_program: Interface<'info, TokenInterface>,
+    token1_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Hop<'info> {
+    // Checks everything the Accounts constraints of swapExactInput would
+    fn load(accounts: &'info [AccountInfo<'info>]) -> Result<Self> {
+        let pool = Account::<Pool>::try_from(&accounts[0])?;
+        if pool.status != PoolStatus::Active {
+            return err!(CustomError::PoolPaused);
+        }
+        if pool.flash_loan_active {
+            return err!(CustomError::FlashLoanActive);
+        }
+
+        let token0 = InterfaceAccount::<Mint>::try_from(&accounts[3])?;
+        let token1 = InterfaceAccount::<Mint>::try_from(&accounts[4])?;
+        if token0.key() != pool.token0 || token1.key() != pool.token1 {
+            return err!(CustomError::InvalidRoute);
+        }
+
+        let token0_program = Interface::<TokenInterface>::try_from(&accounts[7])?;
+        let token1_program = Interface::<TokenInterface>::try_from(&accounts[8])?;
+        if *accounts[3].owner != token0_program.key() || *accounts[4].owner != token1_program.key()
+        {
+            return err!(CustomError::InvalidRoute);
+        }
+
+        let (pool_authority, authority_bump) = Pubkey::find_program_address(
+            &[
+                b"pool_authority",
+                pool.key().as_ref(),
+                pool.token0.as_ref(),
+                pool.token1.as_ref(),
+            ],
+            &crate::ID,
+        );
+        if accounts[2].key() != pool_authority {
+            return err!(CustomError::InvalidRoute);
+        }
+
+        let token0_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[5])?;
+        let token1_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[6])?;
+        if token0_vault.key()
+            != get_associated_token_address_with_program_id(
+                &pool_authority,
+                &pool.token0,
+                &token0_program.key(),
+            )
+            || token1_vault.key()
+                != get_associated_token_address_with_program_id(
+                    &pool_authority,
+                    &pool.token1,
+                    &token1_program.key(),
+                )
+        {
+            return err!(CustomError::InvalidRoute);
+        }
+
+        Ok(Hop {
+            pool,
+            observations: &accounts[1],
+            pool_authority: accounts[2].clone(),
+            authority_bump,
+            token0,
+            token1,
+            token0_vault,
+            token1_vault,
+            token0_program,
+            token1_program,
+        })
+    }
+
+    // (mint, vault, token program) of the side holding `mint`
+    fn side(
+        &self,
+        mint: Pubkey,
+    ) -> (
+        &InterfaceAccount<'info, Mint>,
+        &InterfaceAccount<'info, TokenAccount>,
+        &Interface<'info, TokenInterface>,
+    ) {
+        if mint == self.pool.token0 {
+            (&self.token0, &self.token0_vault, &self.token0_program)
+        } else {
+            (&self.token1, &self.token1_vault, &self.token1_program)
+        }
+    }
+
+    fn other_mint(&self, mint: Pubkey) -> Result<Pubkey> {
+        if mint == self.pool.token0 {
+            Ok(self.pool.token1)
+        } else if mint == self.pool.token1 {
+            Ok(self.pool.token0)
+        } else {
+            err!(CustomError::InvalidRoute)
+        }
+    }
+}
+
+// Swaps `amount_in` of the source mint through every pool in
+// remaining_accounts. Each pool pays its output straight into the next pool's
+// vault, and only the last output is checked against `minimum_amount_out`.
+pub fn swap_route<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<()> {
+    if amount_in == 0 {
+        return err!(CustomError::ZeroAmount);
+    }
+
+    let remaining_accounts = ctx.remaining_accounts;
+    if remaining_accounts.is_empty() || remaining_accounts.len() % ACCOUNTS_PER_HOP != 0 {
+        return err!(CustomError::InvalidRoute);
+    }
+
+    let mut hops = Vec::with_capacity(remaining_accounts.len() / ACCOUNTS_PER_HOP);
+    for accounts in remaining_accounts.chunks(ACCOUNTS_PER_HOP) {
+        let hop = Hop::load(accounts)?;
+        // a second visit would price on balances the first one already moved
+        if hops
+            .iter()
+            .any(|other: &Hop| other.pool.key() == hop.pool.key())
+        {
+            return err!(CustomError::InvalidRoute);
+        }
+        hops.push(hop);
+    }
+
+    // walk the path up front so the destination can be checked before any transfer
+    let mut mint_out = ctx.accounts.user_source.mint;
+    for hop in &hops {
+        mint_out = hop.other_mint(mint_out)?;
+    }
+    if ctx.accounts.user_destination.mint != mint_out {
+        return err!(CustomError::InvalidRoute);
+    }
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    let protocol_fee_bps = ctx.accounts.admin_settings.protocol_fee_bps;
+    let mut mint_in = ctx.accounts.user_source.mint;
+    let mut amount_in = amount_in;
+
+    for i in 0..hops.len() {
+        let mint_out = hops[i].other_mint(mint_in)?;
+        // the vault balances were read before any transfer of this route
+        let destination = match hops.get(i + 1) {
+            Some(next) => next.side(mint_out).1.to_account_info(),
+            None => ctx.accounts.user_destination.to_account_info(),
+        };
+
+        let hop = &mut hops[i];
+        let zero_for_one = mint_in == hop.pool.token0;
+
+        let (reserve0, reserve1) = hop
+            .pool
+            .get_reserves(hop.token0_vault.amount, hop.token1_vault.amount)?;
+        hop.pool.update_price_accumulators(timestamp);
+        let mut observations = Account::<Observations>::try_from(hop.observations)?;
+        if observations.pool != hop.pool.key() {
+            return err!(CustomError::InvalidRoute);
+        }
+        observations.write(&hop.pool, timestamp);
+        observations.exit(&crate::ID)?;
+
+        let (reserve_in, reserve_out) = if zero_for_one {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+
+        let (token_in, vault_in, program_in) = hop.side(mint_in);
+        // later hops are paid what already reached their vault, net of the
+        // transfer fee of the previous hop's output
+        let amount_in_received = if i == 0 {
+            amount_in - get_transfer_fee(&token_in.to_account_info(), amount_in)?
+        } else {
+            amount_in
+        };
+        if i == 0 {
+            token_interface::transfer_checked(
+                CpiContext::new(
+                    program_in.to_account_info(),
+                    TransferChecked {
+                        mint: token_in.to_account_info(),
+                        from: ctx.accounts.user_source.to_account_info(),
+                        to: vault_in.to_account_info(),
+                        authority: ctx.accounts.swapper.to_account_info(),
+                    },
+                ),
+                amount_in,
+                token_in.decimals,
+            )?;
+        }
+
+        let amount_out = quote_amount_out(
+            &hop.pool,
+            amount_in_received,
+            reserve_in,
+            reserve_out,
+            zero_for_one,
+            timestamp,
+        )?;
+
+        let pool_key = hop.pool.key();
+        let authority_seeds = &[
+            "pool_authority".as_bytes(),
+            pool_key.as_ref(),
+            hop.pool.token0.as_ref(),
+            hop.pool.token1.as_ref(),
+            &[hop.authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let (token_out, vault_out, program_out) = hop.side(mint_out);
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                program_out.to_account_info(),
+                TransferChecked {
+                    mint: token_out.to_account_info(),
+                    from: vault_out.to_account_info(),
+                    to: destination,
+                    authority: hop.pool_authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount_out,
+            token_out.decimals,
+        )?;
+        let amount_out_received =
+            amount_out - get_transfer_fee(&token_out.to_account_info(), amount_out)?;
+
+        let (swap_fee, protocol_fee) =
+            get_swap_fees(amount_in_received, hop.pool.fee_bps, protocol_fee_bps)?;
+        let protocol_fees = if zero_for_one {
+            &mut hop.pool.protocol_fees_token0
+        } else {
+            &mut hop.pool.protocol_fees_token1
+        };
+        *protocol_fees = protocol_fees
+            .checked_add(protocol_fee)
+            .ok_or(CustomError::MathOverflow)?;
+
+        let reserve_in_after = reserve_in + amount_in_received - protocol_fee;
+        let reserve_out_after = reserve_out - amount_out;
+        let (reserve0, reserve1) = if zero_for_one {
+            (reserve_in_after, reserve_out_after)
+        } else {
+            (reserve_out_after, reserve_in_after)
+        };
+        hop.pool.reserve0 = reserve0;
+        hop.pool.reserve1 = reserve1;
+        emit!(Swapped {
+            pool: pool_key,
+            swapper: ctx.accounts.swapper.key(),
+            token_in: mint_in,
+            amount_in,
+            amount_out,
+            amount_out_received,
+            swap_fee,
+            protocol_fee,
+            reserve0,
+            reserve1,
+        });
+
+        hop.pool.exit(&crate::ID)?;
+
+        // the next pool is paid what actually reached its vault
+        mint_in = mint_out;
+        amount_in = amount_out_received;
+    }
+
+    // after the last hop amount_in holds what reached the destination
+    if amount_in < minimum_amount_out {
+        return err!(CustomError::SlippageExceeded);
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    #[account(mut)]
+    pub swapper: Signer<'info>,
+
+    #[account(
+        seeds = [b"admin"],
+        bump,
+        constraint = !admin_settings.paused @ CustomError::ProtocolPaused,
+    )]
+    pub admin_settings: Account<'info, AdminSettings>,
+
+    #[account(mut, token::authority = swapper)]
+    pub user_source: InterfaceAccount<'info, TokenAccount>,
+
+    // any account of the final mint, checked against the route
+    #[account(mut)]
+    pub user_destination: InterfaceAccount<'info, TokenAccount>,
+}
@@ -1,15 +1,19 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::Token,
-    token_interface::{self, BurnChecked, Mint, MintTo, Token2022, TokenAccount, TransferChecked},
+use anchor_spl::token_interface::{
+    self, BurnChecked, Mint, TokenAccount, TokenInterface, TransferChecked,
 };
-use fixed::types::I64F64;
 
 use crate::error::*;
+use crate::events::*;
+use crate::math::*;
 use crate::state::*;
 
-pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Result<()> {
+pub fn withdraw_liquidity(
+    mut ctx: Context<WithdrawLiquidity>,
+    amount: u64,
+    min_amount0_out: u64,
+    min_amount1_out: u64,
+) -> Result<()> {
     // calc how much user gets token_0 and token_1
     // burn tokenliq
     // transfer both tokens
@@ -28,17 +32,38 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
     ];
     let signer_seeds = &[&authority_seeds[..]];
 
-    let amount_a = I64F64::from_num(amount)
-        .checked_mul(I64F64::from_num(ctx.accounts.token0_vault.amount))
-        .unwrap()
-        .checked_div(I64F64::from_num(ctx.accounts.tokenliq.supply))
-        .unwrap()
-        .floor()
-        .to_num::<u64>();
+    let (reserve0, reserve1) = ctx.accounts.pool.get_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )?;
+    let timestamp = Clock::get()?.unix_timestamp;
+    ctx.accounts.pool.update_price_accumulators(timestamp);
+    ctx.accounts
+        .observations
+        .write(&ctx.accounts.pool, timestamp);
+
+    // pro-rata on every curve, see curve.rs
+    let amount_a = mul_div(amount, reserve0, ctx.accounts.tokenliq.supply)?;
+
+    let amount_b = mul_div(amount, reserve1, ctx.accounts.tokenliq.supply)?;
+
+    // the minimums apply to what the depositor receives after transfer fees
+    let amount_a_received =
+        amount_a - get_transfer_fee(&ctx.accounts.token0.to_account_info(), amount_a)?;
+    let amount_b_received =
+        amount_b - get_transfer_fee(&ctx.accounts.token1.to_account_info(), amount_b)?;
+
+    if amount_a_received < min_amount0_out {
+        return err!(CustomError::InsufficientAmount0Out);
+    }
+
+    if amount_b_received < min_amount1_out {
+        return err!(CustomError::InsufficientAmount1Out);
+    }
 
     token_interface::transfer_checked(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.token0_program.to_account_info(),
             TransferChecked {
                 mint: ctx.accounts.token0.to_account_info(),
                 from: ctx.accounts.token0_vault.to_account_info(),
@@ -48,20 +73,12 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
             signer_seeds,
         ),
         amount_a,
-        ctx.accounts.token1.decimals,
+        ctx.accounts.token0.decimals,
     )?;
 
-    let amount_b = I64F64::from_num(amount)
-        .checked_mul(I64F64::from_num(ctx.accounts.token1_vault.amount))
-        .unwrap()
-        .checked_div(I64F64::from_num(ctx.accounts.tokenliq.supply))
-        .unwrap()
-        .floor()
-        .to_num::<u64>();
-
     token_interface::transfer_checked(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.token1_program.to_account_info(),
             TransferChecked {
                 mint: ctx.accounts.token1.to_account_info(),
                 from: ctx.accounts.token1_vault.to_account_info(),
@@ -87,30 +104,66 @@ pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, amount: u64) -> Resul
         ctx.accounts.tokenliq.decimals,
     )?;
 
+    let pool = &mut ctx.accounts.pool;
+    pool.liquidity -= amount;
+
+    let (reserve0, reserve1) = get_post_reserves(&mut ctx)?;
+    emit!(LiquidityRemoved {
+        pool: pool_key,
+        depositor: ctx.accounts.depositor.key(),
+        amount0: amount_a,
+        amount1: amount_b,
+        amount0_received: amount_a_received,
+        amount1_received: amount_b_received,
+        liquidity_burned: amount,
+        reserve0,
+        reserve1,
+    });
+
     Ok(())
 }
 
+fn get_post_reserves(ctx: &mut Context<WithdrawLiquidity>) -> Result<(u64, u64)> {
+    ctx.accounts.token0_vault.reload()?;
+    ctx.accounts.token1_vault.reload()?;
+    ctx.accounts.pool.sync_reserves(
+        ctx.accounts.token0_vault.amount,
+        ctx.accounts.token1_vault.amount,
+    )
+}
+
 #[derive(Accounts)]
 pub struct WithdrawLiquidity<'info> {
     #[account(mut)]
     pub depositor: Signer<'info>,
 
+    #[account(mint::token_program = token0_program)]
     pub token0: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token1_program)]
     pub token1: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         seeds = [
             b"pool",
+            pool.amm_config.as_ref(),
             token0.key().as_ref(),
             token1.key().as_ref()
         ],
         bump,
         has_one = token0,
-        has_one = token1
+        has_one = token1,
+        constraint = !pool.flash_loan_active @ CustomError::FlashLoanActive,
     )]
     pub pool: Account<'info, Pool>,
 
+    #[account(
+        mut,
+        seeds = [b"observations", pool.key().as_ref()],
+        bump
+    )]
+    pub observations: Account<'info, Observations>,
+
     /// CHECK: read only
     #[account(
         seeds = [
@@ -127,44 +180,52 @@ pub struct WithdrawLiquidity<'info> {
         mut,
         seeds = [b"tokenliq", pool.key().as_ref(), token0.key().as_ref(), token1.key().as_ref()],
         bump,
+        mint::token_program = token_program,
     )]
     pub tokenliq: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
         associated_token::mint = token0,
-        associated_token::authority = pool_authority
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token0_program,
     )]
     pub token0_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token1,
-        associated_token::authority = pool_authority
+        associated_token::authority = pool_authority,
+        associated_token::token_program = token1_program,
     )]
     pub token1_vault: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token0,
-        associated_token::authority = depositor
+        associated_token::authority = depositor,
+        associated_token::token_program = token0_program,
     )]
     pub depositor_account_0: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = token1,
-        associated_token::authority = depositor
+        associated_token::authority = depositor,
+        associated_token::token_program = token1_program,
     )]
     pub depositor_account_1: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
         associated_token::mint = tokenliq,
-        associated_token::authority = depositor
+        associated_token::authority = depositor,
+        associated_token::token_program = token_program,
     )]
     pub depositor_account_liq: InterfaceAccount<'info, TokenAccount>,
 
     // TODO: check if user can delete his own ata
-    pub token_program: Program<'info, Token>,
+    pub token0_program: Interface<'info, TokenInterface>,
+    pub token1_program: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
 }
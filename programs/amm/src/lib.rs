@@ -2,11 +2,17 @@ use anchor_lang::prelude::*;
 
 declare_id!("3PX9c1PewnSzw69TPCb345uDhGR5FXtdDAAQnrBj7nh4");
 
+mod curve;
 mod error;
+mod events;
 mod instructions;
+mod math;
+mod stable_swap;
 mod state;
+mod weighted_math;
 
 pub use instructions::*;
+pub use state::{CurveType, PoolStatus};
 
 #[program]
 pub mod amm {
@@ -16,23 +22,155 @@ pub mod amm {
         instructions::init_admin(ctx, new_admin)
     }
 
-    pub fn set_admin(ctx: Context<AdminSet>, new_admin: Pubkey) -> Result<()> {
-        instructions::set_admin(ctx, new_admin)
+    pub fn propose_admin(ctx: Context<AdminSet>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AdminAccept>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    pub fn cancel_admin_proposal(ctx: Context<AdminSet>) -> Result<()> {
+        instructions::cancel_admin_proposal(ctx)
+    }
+
+    pub fn grant_role(ctx: Context<RoleGrant>, holder: Pubkey, roles: u8) -> Result<()> {
+        instructions::grant_role(ctx, holder, roles)
+    }
+
+    pub fn revoke_role(ctx: Context<RoleRevoke>, roles: u8) -> Result<()> {
+        instructions::revoke_role(ctx, roles)
+    }
+
+    pub fn set_protocol_fee(ctx: Context<ProtocolFeeSet>, protocol_fee_bps: u16) -> Result<()> {
+        instructions::set_protocol_fee(ctx, protocol_fee_bps)
+    }
+
+    pub fn set_global_pause(ctx: Context<GlobalPauseSet>, paused: bool) -> Result<()> {
+        instructions::set_global_pause(ctx, paused)
+    }
+
+    pub fn set_pool_status(ctx: Context<PoolStatusSet>, status: PoolStatus) -> Result<()> {
+        instructions::set_pool_status(ctx, status)
+    }
+
+    pub fn delist_pool(ctx: Context<PoolDelist>) -> Result<()> {
+        instructions::delist_pool(ctx)
+    }
+
+    pub fn set_pool_creation_config(
+        ctx: Context<AdminSet>,
+        permissionless: bool,
+        creation_fee: u64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::set_pool_creation_config(ctx, permissionless, creation_fee, treasury)
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        instructions::collect_protocol_fees(ctx)
+    }
+
+    pub fn allow_mint(ctx: Context<AllowMint>) -> Result<()> {
+        instructions::allow_mint(ctx)
+    }
+
+    pub fn disallow_mint(ctx: Context<DisallowMint>) -> Result<()> {
+        instructions::disallow_mint(ctx)
+    }
+
+    pub fn create_amm_config(ctx: Context<AmmConfigCreation>, fee_bps: u16) -> Result<()> {
+        instructions::create_amm_config(ctx, fee_bps)
     }
 
     pub fn create_pool(ctx: Context<PoolCreation>) -> Result<()> {
         instructions::create_pool(ctx)
     }
 
-    pub fn deposit(ctx: Context<DepositLiquidity>, amount_a: u64, amount_b: u64) -> Result<()> {
-        instructions::deposit(ctx, amount_a, amount_b)
+    pub fn create_stable_pool(ctx: Context<PoolCreation>, amp: u64) -> Result<()> {
+        instructions::create_stable_pool(ctx, amp)
+    }
+
+    pub fn create_weighted_pool(ctx: Context<PoolCreation>, weight0: u16) -> Result<()> {
+        instructions::create_weighted_pool(ctx, weight0)
+    }
+
+    pub fn ramp_amp(ctx: Context<AmpRamp>, target_amp: u64, ramp_end: i64) -> Result<()> {
+        instructions::ramp_amp(ctx, target_amp, ramp_end)
+    }
+
+    pub fn deposit(
+        ctx: Context<DepositLiquidity>,
+        amount_a: u64,
+        amount_b: u64,
+        min_liquidity_out: u64,
+    ) -> Result<()> {
+        instructions::deposit(ctx, amount_a, amount_b, min_liquidity_out)
+    }
+
+    pub fn withdraw(
+        ctx: Context<WithdrawLiquidity>,
+        amount: u64,
+        min_amount0_out: u64,
+        min_amount1_out: u64,
+    ) -> Result<()> {
+        instructions::withdraw_liquidity(ctx, amount, min_amount0_out, min_amount1_out)
+    }
+
+    pub fn swapExactInput(
+        ctx: Context<SwapExactInput>,
+        amount: u64,
+        minimum_amount_out: u64,
+    ) -> Result<()> {
+        instructions::swapExactInput(ctx, amount, minimum_amount_out)
+    }
+
+    pub fn swap_exact_output(
+        ctx: Context<SwapExactInput>,
+        amount_out: u64,
+        maximum_amount_in: u64,
+    ) -> Result<()> {
+        instructions::swap_exact_output(ctx, amount_out, maximum_amount_in)
+    }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Result<()> {
+        instructions::swap_route(ctx, amount_in, minimum_amount_out)
+    }
+
+    pub fn observe(ctx: Context<Observe>) -> Result<PriceObservation> {
+        instructions::observe(ctx)
+    }
+
+    pub fn get_twap(
+        ctx: Context<Observe>,
+        start_timestamp: i64,
+        end_timestamp: i64,
+    ) -> Result<Twap> {
+        instructions::get_twap(ctx, start_timestamp, end_timestamp)
+    }
+
+    pub fn grow_observations(ctx: Context<ObservationsGrow>, new_capacity: u16) -> Result<()> {
+        instructions::grow_observations(ctx, new_capacity)
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashLoan>, amount0: u64, amount1: u64) -> Result<()> {
+        instructions::flash_borrow(ctx, amount0, amount1)
     }
 
-    pub fn withdraw(ctx: Context<WithdrawLiquidity>, amount: u64) -> Result<()> {
-        instructions::withdraw_liquidity(ctx, amount)
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        instructions::flash_repay(ctx)
     }
 
-    pub fn swapExactInput(ctx: Context<SwapExactInput>, amount: u64) -> Result<()> {
-        instructions::swapExactInput(ctx, amount)
+    pub fn flash_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, FlashSwap<'info>>,
+        amount0_out: u64,
+        amount1_out: u64,
+        callback_data: Vec<u8>,
+    ) -> Result<()> {
+        instructions::flash_swap(ctx, amount0_out, amount1_out, callback_data)
     }
 }
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint as MintState,
+};
+use anchor_spl::token_2022::Token2022;
+use fixed::types::U128F0;
+
+use crate::error::*;
+
+// a * b / c, floored. Widened to u128 so raw amounts of any decimals fit.
+pub fn mul_div(a: u64, b: u64, c: u64) -> Result<u64> {
+    let result = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or(CustomError::MathOverflow)?
+        .checked_div(c as u128)
+        .ok_or(CustomError::MathOverflow)?;
+
+    u64::try_from(result).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// floor(sqrt(a * b)); the product of two u64 always fits in u128
+pub fn sqrt_product(a: u64, b: u64) -> Result<u64> {
+    U128F0::from_num(a as u128 * b as u128)
+        .sqrt()
+        .checked_to_num::<u64>()
+        .ok_or(error!(CustomError::MathOverflow))
+}
+
+// numerator / denominator as Q64.64; a u64 shifted by 64 always fits in u128
+pub fn q64_price(numerator: u64, denominator: u64) -> u128 {
+    ((numerator as u128) << 64) / denominator as u128
+}
+
+// a * b for two Q64.64 values, saturating at u128::MAX
+pub fn q64_mul(a: u128, b: u128) -> u128 {
+    let (high, low) = full_mul(a, b);
+    if high >> 64 != 0 {
+        return u128::MAX;
+    }
+
+    (high << 64) | (low >> 64)
+}
+
+// Full 256-bit product of two u128 as (high, low). Tuples compare
+// lexicographically, so results can be compared directly.
+pub fn full_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // middle limb with the carry out of the low one; three u64 sums fit in u128
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (middle << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+
+    (high, low)
+}
+
+// Token-2022 TransferFeeConfig fee withheld when `amount` is sent from `mint`.
+// Zero for legacy mints and mints without the extension.
+pub fn get_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    match get_transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(error!(CustomError::MathOverflow)),
+        None => Ok(0),
+    }
+}
+
+// Amount that has to be sent from `mint` so that `post_fee_amount` arrives
+pub fn get_pre_fee_amount(mint: &AccountInfo, post_fee_amount: u64) -> Result<u64> {
+    let fee = match get_transfer_fee_config(mint)? {
+        Some(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, post_fee_amount)
+            .ok_or(CustomError::MathOverflow)?,
+        None => 0,
+    };
+
+    post_fee_amount
+        .checked_add(fee)
+        .ok_or(error!(CustomError::MathOverflow))
+}
+
+fn get_transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != Token2022::id() {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    Ok(mint.get_extension::<TransferFeeConfig>().ok().copied())
+}
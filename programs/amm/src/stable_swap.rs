@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+
+use crate::error::*;
+use crate::math::*;
+use crate::state::*;
+
+// Curve's StableSwap invariant for two tokens, with Ann = A * n^n = 4A:
+//   Ann * (x + y) + D = Ann * D + D^3 / (4 * x * y)
+// D and the balances are solved with Newton's method as in Curve's get_D and
+// get_y. Intermediate products are checked u128, so reserves large enough to
+// overflow them fail with MathOverflow instead of wrapping.
+
+const N_COINS: u128 = 2;
+const MAX_ITERATIONS: usize = 255;
+
+// D for balances x and y; equals x + y when they are equal
+pub fn compute_d(amp: u64, x: u64, y: u64) -> Result<u128> {
+    if x == 0 || y == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let (x, y) = (x as u128, y as u128);
+    let sum = x + y;
+    let ann = amp as u128 * N_COINS * N_COINS;
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / (4 * x * y), one factor at a time
+        let d_p = checked_mul(d, d)? / (x * N_COINS);
+        let d_p = checked_mul(d_p, d)? / (y * N_COINS);
+
+        let d_prev = d;
+        let numerator = checked_mul(
+            checked_add(checked_mul(ann, sum)?, checked_mul(d_p, N_COINS)?)?,
+            d,
+        )?;
+        let denominator = checked_add(checked_mul(ann - 1, d)?, checked_mul(N_COINS + 1, d_p)?)?;
+        d = numerator / denominator;
+
+        if d.abs_diff(d_prev) <= 1 {
+            return Ok(d);
+        }
+    }
+
+    err!(CustomError::CurveNotConverged)
+}
+
+// The other balance once one side is `x`, keeping D fixed
+pub fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128> {
+    if x == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let ann = amp as u128 * N_COINS * N_COINS;
+    // c = D^3 / (4 * x * Ann), b = x + D / Ann
+    let c = checked_mul(d, d)? / (x * N_COINS);
+    let c = checked_mul(c, d)? / (ann * N_COINS);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let numerator = checked_add(checked_mul(y, y)?, c)?;
+        let denominator = (2 * y + b)
+            .checked_sub(d)
+            .ok_or(CustomError::MathOverflow)?;
+        y = numerator / denominator;
+
+        if y.abs_diff(y_prev) <= 1 {
+            return Ok(y);
+        }
+    }
+
+    err!(CustomError::CurveNotConverged)
+}
+
+// Output for `amount_in`, with the fee taken from the input before pricing
+// as on the constant product curve. One unit is kept back from the solved
+// balance so Newton's rounding never favours the swapper.
+pub fn get_stable_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+    amp: u64,
+) -> Result<u64> {
+    let amount_in_with_fee = mul_div(amount_in, FEE_DENOMINATOR - fee_bps as u64, FEE_DENOMINATOR)?;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let y = compute_y(amp, reserve_in as u128 + amount_in_with_fee as u128, d)?;
+    let amount_out = (reserve_out as u128).saturating_sub(y).saturating_sub(1);
+
+    u64::try_from(amount_out).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// Inverse of get_stable_amount_out, rounded up
+pub fn get_stable_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+    amp: u64,
+) -> Result<u64> {
+    if amount_out >= reserve_out {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let x = compute_y(amp, (reserve_out - amount_out) as u128, d)?;
+    let amount_in_with_fee = x.saturating_sub(reserve_in as u128) + 1;
+
+    let amount_in = checked_mul(amount_in_with_fee, FEE_DENOMINATOR as u128)?
+        .div_ceil((FEE_DENOMINATOR - fee_bps as u64) as u128);
+
+    u64::try_from(amount_in).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// Marginal price of x in y, -dy/dx on the curve, as Q64.64. With
+// P = D^3 / (4 * x * y) that is y * (Ann * x + P) / (x * (Ann * y + P)):
+// the reserve ratio y / x, pulled towards 1 the higher the amplification.
+pub fn get_stable_spot_price(amp: u64, x: u64, y: u64) -> Result<u128> {
+    let d = compute_d(amp, x, y)?;
+    let ann = amp as u128 * N_COINS * N_COINS;
+    let d_p = checked_mul(d, d)? / (x as u128 * N_COINS);
+    let d_p = checked_mul(d_p, d)? / (y as u128 * N_COINS);
+
+    let numerator = checked_add(checked_mul(ann, x as u128)?, d_p)?;
+    let denominator = checked_add(checked_mul(ann, y as u128)?, d_p)?;
+    // shifted together below 2^64 so the ratio goes through q64_price
+    let shift = (u128::BITS - numerator.max(denominator).leading_zeros()).saturating_sub(64);
+    let tilt = q64_price(
+        (numerator >> shift) as u64,
+        ((denominator >> shift) as u64).max(1),
+    );
+
+    Ok(q64_mul(q64_price(y, x), tilt))
+}
+
+fn checked_mul(a: u128, b: u128) -> Result<u128> {
+    a.checked_mul(b).ok_or(error!(CustomError::MathOverflow))
+}
+
+fn checked_add(a: u128, b: u128) -> Result<u128> {
+    a.checked_add(b).ok_or(error!(CustomError::MathOverflow))
+}
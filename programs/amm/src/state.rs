@@ -1,19 +1,390 @@
+use anchor_lang::prelude::borsh;
 use anchor_lang::prelude::*;
 
+use crate::error::*;
+use crate::math::q64_price;
+use crate::stable_swap::get_stable_spot_price;
+
 #[account]
 #[derive(Default)]
 pub struct AdminSettings {
     pub admin: Pubkey,
+    // share of every swap fee routed to the protocol, in FEE_DENOMINATOR units
+    pub protocol_fee_bps: u16,
+    // kill switch halting swaps and deposits on every pool
+    pub paused: bool,
+    // proposed next admin, Pubkey::default() when there is no proposal
+    pub pending_admin: Pubkey,
+    // when set, anyone may create a pool by paying pool_creation_fee
+    // lamports to the treasury; pool creators still create for free
+    pub permissionless_pool_creation: bool,
+    pub pool_creation_fee: u64,
+    pub treasury: Pubkey,
+}
+
+impl AdminSettings {
+    pub const INIT_SPACE: usize = 108;
+}
+
+// Roles held by `holder`, as a bitmask of the constants below
+#[account]
+#[derive(Default)]
+pub struct RoleAssignment {
+    pub holder: Pubkey,
+    pub roles: u8,
+}
+
+impl RoleAssignment {
+    pub const INIT_SPACE: usize = 33;
+
+    pub const POOL_CREATOR: u8 = 1 << 0;
+    pub const FEE_MANAGER: u8 = 1 << 1;
+    pub const PAUSER: u8 = 1 << 2;
+    pub const TREASURY_WITHDRAWER: u8 = 1 << 3;
+    pub const ALL: u8 =
+        Self::POOL_CREATOR | Self::FEE_MANAGER | Self::PAUSER | Self::TREASURY_WITHDRAWER;
+
+    pub fn has(&self, role: u8) -> bool {
+        self.roles & role == role
+    }
+}
+
+// Exists for mints the admin has cleared despite a freeze authority or a
+// risky Token-2022 extension
+#[account]
+#[derive(Default)]
+pub struct AllowedMint {
+    pub mint: Pubkey,
+}
+
+impl AllowedMint {
+    pub const INIT_SPACE: usize = 32;
+}
+
+// A fee tier. Pools are seeded by their config, so one pair can have a pool
+// per tier.
+#[account]
+#[derive(Default)]
+pub struct AmmConfig {
+    pub fee_bps: u16,
+}
+
+impl AmmConfig {
+    pub const INIT_SPACE: usize = 2;
+}
+
+// Withdrawals stay open in every status so LPs can always exit.
+// Delisting is final and only the admin can do it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum PoolStatus {
+    #[default]
+    Active,
+    Paused,
+    Delisted,
+}
+
+// Pricing curve of a pool, chosen at creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum CurveType {
+    // x * y = k
+    #[default]
+    ConstantProduct,
+    // Curve's StableSwap invariant for pegged pairs, flat around a 1:1 price
+    // and steeper the lower the amplification
+    StableSwap,
+    // Balancer's weighted product x^w0 * y^w1 = k, with the weights on the
+    // pool; 50/50 prices the same as ConstantProduct
+    Weighted,
 }
 
 #[account]
 #[derive(Default)]
 pub struct Pool {
+    pub amm_config: Pubkey,
     pub token0: Pubkey,
     pub token1: Pubkey,
     pub liquidity: u64,
+    // copied from amm_config on creation
+    pub fee_bps: u16,
+    // uncollected protocol fees, held in the vaults but not part of the reserves
+    pub protocol_fees_token0: u64,
+    pub protocol_fees_token1: u64,
+    pub status: PoolStatus,
+    // sums of price * seconds since creation, Uniswap V2 style. Prices are
+    // Q64.64 (token1 per token0 and the inverse) and the sums wrap, so only
+    // differences between two observations are meaningful.
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    pub last_update_timestamp: i64,
+    // set by flash_borrow until the matching flash_repay, and by flash_swap
+    // around its callback; swaps and liquidity changes are refused meanwhile
+    // since the vaults are short the loan
+    pub flash_loan_active: bool,
+    pub flash_loan_amount0: u64,
+    pub flash_loan_amount1: u64,
+    pub curve_type: CurveType,
+    // StableSwap amplification, moving linearly from amp_initial at
+    // amp_ramp_start to amp_target at amp_ramp_end. Unused by other curves.
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_ramp_start: i64,
+    pub amp_ramp_end: i64,
+    // share of the pool's value held in each token, in bps of
+    // WEIGHT_DENOMINATOR; 5_000 each on the other curves
+    pub weight0: u16,
+    pub weight1: u16,
+    // reserves as of the end of the last instruction that moved them, Uniswap
+    // V2 style. The accumulators price from these and never from the vault
+    // balances, which a plain transfer into a vault can move without the pool
+    // seeing it.
+    pub reserve0: u64,
+    pub reserve1: u64,
 }
 
 impl Pool {
-    pub const INIT_SPACE: usize = 72;
+    pub const INIT_SPACE: usize = 233;
+    pub const MAX_FEE_BPS: u16 = 1_000;
+
+    pub const MIN_AMP: u64 = 1;
+    pub const MAX_AMP: u64 = 10_000;
+    // a ramp lasts at least a day and moves A by at most this factor
+    pub const MIN_AMP_RAMP_DURATION: i64 = 86_400;
+    pub const MAX_AMP_CHANGE: u64 = 10;
+
+    pub const WEIGHT_DENOMINATOR: u16 = 10_000;
+    pub const EQUAL_WEIGHT: u16 = 5_000;
+    // caps the weight ratio, and with it the exponent of the weighted math, at 99
+    pub const MIN_WEIGHT: u16 = 100;
+
+    // Vault balances minus what is owed to the protocol
+    pub fn get_reserves(&self, vault0_amount: u64, vault1_amount: u64) -> Result<(u64, u64)> {
+        let reserve0 = vault0_amount
+            .checked_sub(self.protocol_fees_token0)
+            .ok_or(CustomError::MathOverflow)?;
+        let reserve1 = vault1_amount
+            .checked_sub(self.protocol_fees_token1)
+            .ok_or(CustomError::MathOverflow)?;
+
+        Ok((reserve0, reserve1))
+    }
+
+    // (weight_in, weight_out) for a swap in the given direction
+    pub fn get_weights(&self, zero_for_one: bool) -> (u16, u16) {
+        if zero_for_one {
+            (self.weight0, self.weight1)
+        } else {
+            (self.weight1, self.weight0)
+        }
+    }
+
+    // Amplification in effect at `timestamp`
+    pub fn get_amp(&self, timestamp: i64) -> u64 {
+        if timestamp >= self.amp_ramp_end || self.amp_ramp_end <= self.amp_ramp_start {
+            return self.amp_target;
+        }
+
+        let elapsed = timestamp.saturating_sub(self.amp_ramp_start).max(0) as u128;
+        let duration = (self.amp_ramp_end - self.amp_ramp_start) as u128;
+        let initial = self.amp_initial as u128;
+        let target = self.amp_target as u128;
+        let amp = if target > initial {
+            initial + (target - initial) * elapsed / duration
+        } else {
+            initial - (initial - target) * elapsed / duration
+        };
+
+        amp as u64
+    }
+
+    // Records the reserves the instruction leaves behind; call once the vaults
+    // hold their final balances and the protocol fees are accrued
+    pub fn sync_reserves(&mut self, vault0_amount: u64, vault1_amount: u64) -> Result<(u64, u64)> {
+        (self.reserve0, self.reserve1) = self.get_reserves(vault0_amount, vault1_amount)?;
+        Ok((self.reserve0, self.reserve1))
+    }
+
+    // Accumulators as of `timestamp`, the stored reserves having held since
+    // the last update
+    pub fn get_cumulative_prices(&self, timestamp: i64) -> (u128, u128) {
+        let elapsed = timestamp.saturating_sub(self.last_update_timestamp);
+        if elapsed <= 0 || self.reserve0 == 0 || self.reserve1 == 0 {
+            return (self.price0_cumulative, self.price1_cumulative);
+        }
+
+        let elapsed = elapsed as u128;
+        let (price0, price1) = self.get_spot_prices(self.reserve0, self.reserve1, timestamp);
+        (
+            self.price0_cumulative
+                .wrapping_add(price0.wrapping_mul(elapsed)),
+            self.price1_cumulative
+                .wrapping_add(price1.wrapping_mul(elapsed)),
+        )
+    }
+
+    // Q64.64 prices of token0 in token1 and the inverse. On a weighted pool
+    // the spot price is (reserve1 / weight1) / (reserve0 / weight0); with
+    // equal weights that is the reserve ratio. A stable pool prices at the
+    // slope of its curve under the amplification in effect at `timestamp`.
+    fn get_spot_prices(&self, reserve0: u64, reserve1: u64, timestamp: i64) -> (u128, u128) {
+        let price0 = q64_price(reserve1, reserve0);
+        let price1 = q64_price(reserve0, reserve1);
+
+        match self.curve_type {
+            CurveType::StableSwap => {
+                let amp = self.get_amp(timestamp);
+                // D only fails to converge on reserves no swap could leave
+                // behind; the reserve ratio stands in for those
+                (
+                    get_stable_spot_price(amp, reserve0, reserve1).unwrap_or(price0),
+                    get_stable_spot_price(amp, reserve1, reserve0).unwrap_or(price1),
+                )
+            }
+            CurveType::Weighted if self.weight0 != self.weight1 => {
+                let (weight0, weight1) = (self.weight0 as u128, self.weight1 as u128);
+                (
+                    (price0 / weight1).saturating_mul(weight0),
+                    (price1 / weight0).saturating_mul(weight1),
+                )
+            }
+            _ => (price0, price1),
+        }
+    }
+
+    // Call before the instruction moves the reserves and sync_reserves after,
+    // so a trade only affects the price from the next second on
+    pub fn update_price_accumulators(&mut self, timestamp: i64) {
+        (self.price0_cumulative, self.price1_cumulative) = self.get_cumulative_prices(timestamp);
+        self.last_update_timestamp = timestamp;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    // sum of pool.liquidity * seconds, wrapping like the price sums
+    pub liquidity_cumulative: u128,
+    pub initialized: bool,
+}
+
+impl Observation {
+    pub const SIZE: usize = 57;
+}
+
+// Ring buffer of the pool's accumulators, one sample per second with
+// activity. Uniswap V3 style: capacity grows through grow_observations and
+// only takes effect once the write index reaches the end of the active ring.
+#[account]
+#[derive(Default)]
+pub struct Observations {
+    pub pool: Pubkey,
+    // slot of the newest observation
+    pub index: u16,
+    // slots in the active ring, at most observations.len()
+    pub cardinality: u16,
+    pub observations: Vec<Observation>,
+}
+
+impl Observations {
+    // keeps a swap's deserialization within the 32KB heap
+    pub const MAX_CAPACITY: u16 = 256;
+
+    pub fn space(capacity: usize) -> usize {
+        32 + 2 + 2 + 4 + capacity * Observation::SIZE
+    }
+
+    // Call right after pool.update_price_accumulators, before the
+    // instruction changes pool.liquidity
+    pub fn write(&mut self, pool: &Pool, timestamp: i64) {
+        let last = self.observations[self.index as usize];
+        if last.timestamp == timestamp {
+            return;
+        }
+
+        if self.index == self.cardinality - 1 && self.observations.len() > self.cardinality as usize
+        {
+            self.cardinality = self.observations.len() as u16;
+        }
+        self.index = (self.index + 1) % self.cardinality;
+        self.observations[self.index as usize] = Observation {
+            timestamp,
+            price0_cumulative: pool.price0_cumulative,
+            price1_cumulative: pool.price1_cumulative,
+            liquidity_cumulative: get_liquidity_cumulative(&last, pool.liquidity, timestamp),
+            initialized: true,
+        };
+    }
+
+    // Accumulators at `timestamp`, interpolated between the two samples around
+    // it or carried forward from the newest one with the stored reserves
+    pub fn observe_at(&self, pool: &Pool, timestamp: i64) -> Result<Observation> {
+        let newest = self.observations[self.index as usize];
+        if timestamp >= newest.timestamp {
+            let (price0_cumulative, price1_cumulative) = pool.get_cumulative_prices(timestamp);
+            return Ok(Observation {
+                timestamp,
+                price0_cumulative,
+                price1_cumulative,
+                liquidity_cumulative: get_liquidity_cumulative(&newest, pool.liquidity, timestamp),
+                initialized: true,
+            });
+        }
+
+        // until the ring wraps, the slots after the newest are still empty
+        let cardinality = self.cardinality as usize;
+        let next = (self.index as usize + 1) % cardinality;
+        let (oldest, len) = if self.observations[next].initialized {
+            (next, cardinality)
+        } else {
+            (0, self.index as usize + 1)
+        };
+        let at = |i: usize| self.observations[(oldest + i) % cardinality];
+
+        if timestamp < at(0).timestamp {
+            return err!(CustomError::ObservationTooOld);
+        }
+
+        // largest sample at or before `timestamp`; the newest is after it
+        let (mut low, mut high) = (0, len - 1);
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if at(mid).timestamp <= timestamp {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        let (before, after) = (at(low), at(high));
+
+        // price and liquidity are constant between two samples, so each
+        // difference divides evenly by the time between them
+        let elapsed = (after.timestamp - before.timestamp) as u128;
+        let target_elapsed = (timestamp - before.timestamp) as u128;
+        let interpolate = |from: u128, to: u128| {
+            from.wrapping_add(to.wrapping_sub(from) / elapsed * target_elapsed)
+        };
+
+        Ok(Observation {
+            timestamp,
+            price0_cumulative: interpolate(before.price0_cumulative, after.price0_cumulative),
+            price1_cumulative: interpolate(before.price1_cumulative, after.price1_cumulative),
+            liquidity_cumulative: interpolate(
+                before.liquidity_cumulative,
+                after.liquidity_cumulative,
+            ),
+            initialized: true,
+        })
+    }
 }
+
+fn get_liquidity_cumulative(last: &Observation, liquidity: u64, timestamp: i64) -> u128 {
+    let elapsed = timestamp.saturating_sub(last.timestamp).max(0) as u128;
+    last.liquidity_cumulative
+        .wrapping_add((liquidity as u128).wrapping_mul(elapsed))
+}
+
+pub const FEE_DENOMINATOR: u64 = 10_000;
+
+// LP tokens minted to the pool authority on creation and never redeemable
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
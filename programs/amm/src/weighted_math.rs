@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+use fixed::types::{I64F64, U64F64};
+
+use crate::error::*;
+use crate::math::*;
+use crate::state::*;
+
+// Balancer's weighted product for two tokens, x^w0 * y^w1 = k with
+// w0 + w1 = 1. Pricing needs powers with fractional exponents, which the
+// fixed crate doesn't provide, so x^y is taken as 2^(y * log2(x)) on 64.64
+// fixed point. Both steps are good to about 2^-56; every power is pushed up
+// by a relative 2^-POW_MARGIN_SHIFT before use so the error always lands on
+// the swapper's side.
+
+const POW_MARGIN_SHIFT: u32 = 40;
+// log2 of the invariant can come out up to this much off, 2^-50
+const LOG_TOLERANCE: I64F64 = I64F64::from_bits(1 << 14);
+const TWO: U64F64 = U64F64::from_bits(2 << 64);
+
+// Output for `amount_in`, fee taken from the input first as on the other curves:
+//   out = reserve_out * (1 - (reserve_in / (reserve_in + in))^(weight_in / weight_out))
+pub fn get_weighted_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+    weight_in: u16,
+    weight_out: u16,
+) -> Result<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let amount_in_with_fee = mul_div(amount_in, FEE_DENOMINATOR - fee_bps as u64, FEE_DENOMINATOR)?;
+
+    let base = ratio(reserve_in, reserve_in as u128 + amount_in_with_fee as u128);
+    let power = pow_up(base, weight_ratio(weight_in, weight_out))?;
+    let amount_out = U64F64::from_num(reserve_out)
+        .checked_mul(U64F64::ONE.saturating_sub(power))
+        .ok_or(CustomError::MathOverflow)?;
+
+    Ok(amount_out.to_num())
+}
+
+// Inverse of get_weighted_amount_out, rounded up:
+//   in = reserve_in * ((reserve_out / (reserve_out - out))^(weight_out / weight_in) - 1)
+pub fn get_weighted_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_bps: u16,
+    weight_in: u16,
+    weight_out: u16,
+) -> Result<u64> {
+    if amount_out >= reserve_out {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let base = ratio(reserve_out, (reserve_out - amount_out) as u128);
+    let power = pow_up(base, weight_ratio(weight_out, weight_in))?;
+    let amount_in_with_fee: u64 = U64F64::from_num(reserve_in)
+        .checked_mul(power.saturating_sub(U64F64::ONE))
+        .and_then(|amount| amount.checked_ceil())
+        .and_then(|amount| amount.checked_to_num())
+        .ok_or(CustomError::MathOverflow)?;
+
+    let amount_in = (amount_in_with_fee as u128 * FEE_DENOMINATOR as u128)
+        .div_ceil((FEE_DENOMINATOR - fee_bps as u64) as u128);
+
+    u64::try_from(amount_in).map_err(|_| error!(CustomError::MathOverflow))
+}
+
+// x^w0 * y^w1 of the balances, the geometric mean sqrt(x * y) at 50/50.
+// Rounded down past the error of the logs, so it can come out a unit low.
+pub fn get_weighted_invariant(pool: &Pool, balance0: u64, balance1: u64) -> Result<u64> {
+    let log_invariant = log2_invariant(pool, balance0, balance1)?;
+    let invariant = exp2(log_invariant.saturating_sub(LOG_TOLERANCE))?;
+
+    Ok(invariant.to_num())
+}
+
+// Whether the invariant of `balances` is at least that of `reserves`. The
+// comparison is in log space and only passes with LOG_TOLERANCE to spare.
+pub fn weighted_invariant_holds(
+    pool: &Pool,
+    reserves: (u64, u64),
+    balances: (u64, u64),
+) -> Result<bool> {
+    let before = log2_invariant(pool, reserves.0, reserves.1)?;
+    let after = log2_invariant(pool, balances.0, balances.1)?;
+
+    Ok(after >= before.saturating_add(LOG_TOLERANCE))
+}
+
+// w0 * log2(x) + w1 * log2(y)
+fn log2_invariant(pool: &Pool, balance0: u64, balance1: u64) -> Result<I64F64> {
+    let denominator = I64F64::from_num(Pool::WEIGHT_DENOMINATOR);
+    let weight0 = I64F64::from_num(pool.weight0) / denominator;
+    let weight1 = I64F64::from_num(pool.weight1) / denominator;
+
+    let log0 = log2(U64F64::from_num(balance0))?;
+    let log1 = log2(U64F64::from_num(balance1))?;
+
+    Ok(log0 * weight0 + log1 * weight1)
+}
+
+// base^exponent, rounded up by the margin
+fn pow_up(base: U64F64, exponent: U64F64) -> Result<U64F64> {
+    let exponent = I64F64::checked_from_num(exponent).ok_or(CustomError::MathOverflow)?;
+    let log = log2(base)?
+        .checked_mul(exponent)
+        .ok_or(CustomError::MathOverflow)?;
+    let power = exp2(log)?;
+
+    power
+        .checked_add((power >> POW_MARGIN_SHIFT) + U64F64::DELTA)
+        .ok_or(error!(CustomError::MathOverflow))
+}
+
+// log2(x) for x > 0: the integer part from the leading bit, then one bit of
+// the fraction per squaring of the mantissa
+fn log2(x: U64F64) -> Result<I64F64> {
+    if x == U64F64::ZERO {
+        return err!(CustomError::InvalidLiquidity);
+    }
+
+    let int_part = x.int_log2();
+    // in [1, 2)
+    let mut mantissa = if int_part >= 0 {
+        x >> int_part.unsigned_abs()
+    } else {
+        x << int_part.unsigned_abs()
+    };
+
+    let mut result = I64F64::from_num(int_part);
+    let mut bit = I64F64::ONE >> 1u32;
+    while bit > I64F64::ZERO {
+        // below 4, so no overflow
+        mantissa *= mantissa;
+        if mantissa >= TWO {
+            mantissa >>= 1u32;
+            result += bit;
+        }
+        bit >>= 1u32;
+    }
+
+    Ok(result)
+}
+
+// 2^x: the integer part as a shift, the fraction from the series of e^(f * ln 2)
+fn exp2(x: I64F64) -> Result<U64F64> {
+    let int_part: i32 = x
+        .floor()
+        .checked_to_num()
+        .ok_or(CustomError::MathOverflow)?;
+    if int_part >= 64 {
+        return err!(CustomError::MathOverflow);
+    }
+    if int_part < -64 {
+        return Ok(U64F64::ZERO);
+    }
+
+    let z = U64F64::from_num(x.frac()) * U64F64::LN_2;
+    let mut term = U64F64::ONE;
+    let mut sum = U64F64::ONE;
+    let mut k: u128 = 1;
+    while term > U64F64::ZERO {
+        term = term * z / k;
+        sum += term;
+        k += 1;
+    }
+
+    // sum is in [1, 2)
+    if int_part >= 0 {
+        sum.checked_mul(U64F64::from_bits(1 << (64 + int_part)))
+            .ok_or(error!(CustomError::MathOverflow))
+    } else {
+        Ok(sum >> int_part.unsigned_abs())
+    }
+}
+
+// numerator / denominator; callers keep it below 2^64
+fn ratio(numerator: u64, denominator: u128) -> U64F64 {
+    U64F64::from_bits(((numerator as u128) << 64) / denominator)
+}
+
+fn weight_ratio(numerator: u16, denominator: u16) -> U64F64 {
+    U64F64::from_num(numerator) / U64F64::from_num(denominator)
+}
@@ -3,13 +3,17 @@ use litesvm::LiteSVM;
 use litesvm_token::{spl_token::native_mint::DECIMALS, CreateAssociatedTokenAccount, CreateMint};
 use sha2::{Digest, Sha256};
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
+    instruction::{AccountMeta, Instruction, InstructionError},
     pubkey::Pubkey,
     signature::{read_keypair_file, Keypair, Signer},
     transaction::{Transaction, TransactionError},
 };
 use solana_system_interface::program::ID as system_program_id;
 use spl_associated_token_account::get_associated_token_address;
+use spl_token_2022_interface::{
+    extension::{transfer_hook, ExtensionType},
+    instruction::{initialize_non_transferable_mint, initialize_permanent_delegate},
+};
 
 mod utils;
 
@@ -20,9 +24,25 @@ struct AdminSettings {
 
 #[derive(Debug, BorshDeserialize)]
 struct Pool {
+    pub amm_config: Pubkey,
     pub token0: Pubkey,
     pub token1: Pubkey,
     pub liquidity: u64,
+    pub fee_bps: u16,
+    pub protocol_fees_token0: u64,
+    pub protocol_fees_token1: u64,
+    pub status: u8,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct PoolCreated {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub fee_bps: u16,
+    pub creation_fee: u64,
 }
 
 #[test]
@@ -33,7 +53,7 @@ fn test_create_pool() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
 
@@ -68,23 +88,576 @@ fn test_create_pool() {
             .unwrap();
     }
 
+    let fee_bps = 30;
+
     let tx_result =
-        utils::handlers::handle_create_pool(&mut svm, &program_id, &admin, &token0, &token1);
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
     assert!(
         tx_result.is_ok(),
-        "Create transaction faile: {:?}",
+        "Create amm config transaction failed: {:?}",
         tx_result.err()
     );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction faile: {:?}",
+        tx_result.as_ref().err()
+    );
 
     let (pool_pda, _bump_pool_pda) =
-        utils::get_utils::get_pool_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
     let account_pool = svm.get_account(&pool_pda).expect("Should exist");
 
     let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
 
-    assert_eq!(data.liquidity, 0);
+    assert_eq!(data.amm_config, amm_config);
+    assert_eq!(data.liquidity, utils::get_utils::MINIMUM_LIQUIDITY);
     assert_eq!(data.token0, token0);
     assert_eq!(data.token1, token1);
+    assert_eq!(data.fee_bps, fee_bps);
+
+    let events: Vec<PoolCreated> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "PoolCreated");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].creator, admin.pubkey());
+    assert_eq!(events[0].amm_config, amm_config);
+    assert_eq!(events[0].token0, token0);
+    assert_eq!(events[0].token1, token1);
+    assert_eq!(events[0].fee_bps, fee_bps);
+    // the admin holds POOL_CREATOR and creates for free
+    assert_eq!(events[0].creation_fee, 0);
+}
+
+#[test]
+fn test_create_pool_freeze_authority_needs_allowlist() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+    helper_set_admin(&program_id, &admin, &admin.pubkey(), &mut svm);
+
+    // token0 can be frozen by its issuer, token1 is a plain mint
+    let token0 = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .freeze_authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let mut token1 = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    while token1 <= token0 {
+        token1 = CreateMint::new(&mut svm, &admin)
+            .authority(&admin.pubkey())
+            .decimals(DECIMALS)
+            .send()
+            .unwrap();
+    }
+
+    let fee_bps = 30;
+
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MintHasFreezeAuthority
+        TransactionError::InstructionError(0, InstructionError::Custom(6010)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // only the admin maintains the allowlist
+    let tx_result = utils::handlers::handle_allow_mint(&mut svm, &program_id, &user, &token0);
+    assert!(tx_result.is_err());
+
+    let tx_result = utils::handlers::handle_allow_mint(&mut svm, &program_id, &admin, &token0);
+    assert!(
+        tx_result.is_ok(),
+        "Allow mint transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    svm.expire_blockhash();
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let tx_result = utils::handlers::handle_disallow_mint(&mut svm, &program_id, &admin, &token0);
+    assert!(
+        tx_result.is_ok(),
+        "Disallow mint transaction failed: {:?}",
+        tx_result.err()
+    );
+    assert!(!utils::handlers::is_mint_allowed(
+        &svm,
+        &program_id,
+        &token0
+    ));
+}
+
+#[test]
+fn test_create_pool_token_2022_extensions_need_allowlist() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+
+    helper_set_admin(&program_id, &admin, &admin.pubkey(), &mut svm);
+
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // the issuer can move or burn anyone's tokens, the vaults included
+    let mint = Keypair::new();
+    let permanent_delegate_mint = utils::setup_utils::create_token_2022_mint(
+        &mut svm,
+        &admin,
+        &mint,
+        &admin.pubkey(),
+        &DECIMALS,
+        &[ExtensionType::PermanentDelegate],
+        &[initialize_permanent_delegate(
+            &spl_token_2022_interface::id(),
+            &mint.pubkey(),
+            &admin.pubkey(),
+        )
+        .unwrap()],
+    );
+    let (token0, token1) = helper_pair_with_plain_mint(&mut svm, &admin, &permanent_delegate_mint);
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MintHasPermanentDelegate
+        TransactionError::InstructionError(0, InstructionError::Custom(6011)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // the vaults couldn't pay out
+    let mint = Keypair::new();
+    let non_transferable_mint = utils::setup_utils::create_token_2022_mint(
+        &mut svm,
+        &admin,
+        &mint,
+        &admin.pubkey(),
+        &DECIMALS,
+        &[ExtensionType::NonTransferable],
+        &[
+            initialize_non_transferable_mint(&spl_token_2022_interface::id(), &mint.pubkey())
+                .unwrap(),
+        ],
+    );
+    let (token0, token1) = helper_pair_with_plain_mint(&mut svm, &admin, &non_transferable_mint);
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MintNonTransferable
+        TransactionError::InstructionError(0, InstructionError::Custom(6013)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // a hook program runs on every transfer out of the vaults
+    let mint = Keypair::new();
+    let transfer_hook_mint = utils::setup_utils::create_token_2022_mint(
+        &mut svm,
+        &admin,
+        &mint,
+        &admin.pubkey(),
+        &DECIMALS,
+        &[ExtensionType::TransferHook],
+        &[transfer_hook::instruction::initialize(
+            &spl_token_2022_interface::id(),
+            &mint.pubkey(),
+            Some(admin.pubkey()),
+            Some(Pubkey::new_unique()),
+        )
+        .unwrap()],
+    );
+    let (token0, token1) = helper_pair_with_plain_mint(&mut svm, &admin, &transfer_hook_mint);
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MintHasTransferHook
+        TransactionError::InstructionError(0, InstructionError::Custom(6012)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // without a program the hook extension does nothing
+    let mint = Keypair::new();
+    let inert_hook_mint = utils::setup_utils::create_token_2022_mint(
+        &mut svm,
+        &admin,
+        &mint,
+        &admin.pubkey(),
+        &DECIMALS,
+        &[ExtensionType::TransferHook],
+        &[transfer_hook::instruction::initialize(
+            &spl_token_2022_interface::id(),
+            &mint.pubkey(),
+            Some(admin.pubkey()),
+            None,
+        )
+        .unwrap()],
+    );
+    let (token0, token1) = helper_pair_with_plain_mint(&mut svm, &admin, &inert_hook_mint);
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // the allowlist vouches for the issuer
+    let tx_result =
+        utils::handlers::handle_allow_mint(&mut svm, &program_id, &admin, &permanent_delegate_mint);
+    assert!(
+        tx_result.is_ok(),
+        "Allow mint transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let (token0, token1) = helper_pair_with_plain_mint(&mut svm, &admin, &permanent_delegate_mint);
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+}
+
+#[test]
+fn test_permissionless_pool_creation() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let treasury = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+    helper_set_admin(&program_id, &admin, &admin.pubkey(), &mut svm);
+
+    let token0 = CreateMint::new(&mut svm, &user)
+        .authority(&user.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let mut token1 = CreateMint::new(&mut svm, &user)
+        .authority(&user.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    while token1 <= token0 {
+        token1 = CreateMint::new(&mut svm, &user)
+            .authority(&user.pubkey())
+            .decimals(DECIMALS)
+            .send()
+            .unwrap();
+    }
+
+    // without the role and with permissionless mode off, user is turned away
+    let fee_bps = 30;
+
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &user,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::PermissionlessCreationDisabled
+        TransactionError::InstructionError(0, InstructionError::Custom(6018)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let creation_fee = 500_000_000;
+    let tx_result = utils::handlers::handle_set_pool_creation_config(
+        &mut svm,
+        &program_id,
+        &admin,
+        &true,
+        &creation_fee,
+        &treasury.pubkey(),
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Set pool creation config transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    svm.expire_blockhash();
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &user,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let treasury_account = svm.get_account(&treasury.pubkey()).expect("Should exist");
+    assert_eq!(treasury_account.lamports, creation_fee);
+
+    // the admin can still take the pool down for good
+    let tx_result = utils::handlers::handle_delist_pool(
+        &mut svm,
+        &program_id,
+        &user,
+        &amm_config,
+        &token0,
+        &token1,
+    );
+    assert!(tx_result.is_err());
+
+    let tx_result = utils::handlers::handle_delist_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Delist transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let (pool_pda, _bump_pool_pda) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let account_pool = svm.get_account(&pool_pda).expect("Should exist");
+    let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
+    assert_eq!(data.status, 2);
+
+    // and a pauser can't bring it back
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &0,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::PoolDelisted
+        TransactionError::InstructionError(0, InstructionError::Custom(6019)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_fee_tiers_per_pair() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+    helper_set_admin(&program_id, &admin, &admin.pubkey(), &mut svm);
+
+    let token0 = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    let mut token1 = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    while token1 <= token0 {
+        token1 = CreateMint::new(&mut svm, &admin)
+            .authority(&admin.pubkey())
+            .decimals(DECIMALS)
+            .send()
+            .unwrap();
+    }
+
+    // only fee managers manage tiers
+    let tx_result = utils::handlers::handle_grant_role(
+        &mut svm,
+        &program_id,
+        &admin,
+        &user.pubkey(),
+        &utils::get_utils::PAUSER,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Grant role transaction failed: {:?}",
+        tx_result.err()
+    );
+    let tx_result = utils::handlers::handle_create_amm_config(&mut svm, &program_id, &user, &30);
+    match tx_result.unwrap_err().err {
+        // CustomError::MissingRole
+        TransactionError::InstructionError(0, InstructionError::Custom(6016)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &1_001);
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidFee
+        TransactionError::InstructionError(0, InstructionError::Custom(6005)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    for fee_bps in [1, 5, 30, 100] {
+        let tx_result =
+            utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+        assert!(
+            tx_result.is_ok(),
+            "Create amm config transaction failed: {:?}",
+            tx_result.err()
+        );
+
+        let tx_result = utils::handlers::handle_create_pool(
+            &mut svm,
+            &program_id,
+            &admin,
+            &token0,
+            &token1,
+            &fee_bps,
+        );
+        assert!(
+            tx_result.is_ok(),
+            "Create transaction failed: {:?}",
+            tx_result.err()
+        );
+    }
+
+    for fee_bps in [1, 5, 30, 100] {
+        let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+        let (pool_pda, _bump_pool_pda) =
+            utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+        let account_pool = svm.get_account(&pool_pda).expect("Should exist");
+        let data =
+            Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
+
+        assert_eq!(data.amm_config, amm_config);
+        assert_eq!(data.fee_bps, fee_bps);
+    }
+
+    // a tier's fee is fixed, so it can't be created twice
+    svm.expire_blockhash();
+    let tx_result = utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &30);
+    assert!(tx_result.is_err());
 }
 
 fn helper_set_admin(program_id: &Pubkey, admin: &Keypair, admin_pub: &Pubkey, svm: &mut LiteSVM) {
@@ -98,3 +671,22 @@ fn helper_set_admin(program_id: &Pubkey, admin: &Keypair, admin_pub: &Pubkey, sv
 
     let tx_result = svm.send_transaction(tx);
 }
+
+// Orders `mint` with a fresh plain mint into (token0, token1)
+fn helper_pair_with_plain_mint(
+    svm: &mut LiteSVM,
+    admin: &Keypair,
+    mint: &Pubkey,
+) -> (Pubkey, Pubkey) {
+    let plain_mint = CreateMint::new(svm, admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+
+    if *mint < plain_mint {
+        (*mint, plain_mint)
+    } else {
+        (plain_mint, *mint)
+    }
+}
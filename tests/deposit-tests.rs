@@ -3,13 +3,16 @@ use anchor_spl::token_2022::ID as TOKEN_2022_ID;
 use borsh::BorshDeserialize;
 use litesvm::LiteSVM;
 use litesvm_token::{
-    spl_token::{native_mint::DECIMALS, state::Account},
+    spl_token::{
+        native_mint::DECIMALS,
+        state::{Account, Mint},
+    },
     CreateAssociatedTokenAccount, CreateMint, MintTo,
 };
 use sha2::{Digest, Sha256};
 use solana_program::program_pack::Pack;
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
+    instruction::{AccountMeta, Instruction, InstructionError},
     pubkey::Pubkey,
     signature::{read_keypair_file, Keypair, Signer},
     transaction::{Transaction, TransactionError},
@@ -30,9 +33,22 @@ struct AdminSettings {
 
 #[derive(Debug, BorshDeserialize)]
 struct Pool {
+    pub amm_config: Pubkey,
     pub token0: Pubkey,
     pub token1: Pubkey,
     pub liquidity: u64,
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub liquidity_minted: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
 }
 
 #[test]
@@ -45,7 +61,7 @@ fn test_successful_deposit() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
     svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
@@ -78,8 +94,24 @@ fn test_successful_deposit() {
             .unwrap();
     }
 
-    let tx_createPool_result =
-        utils::handlers::handle_create_pool(&mut svm, &program_id, &admin, &token0, &token1);
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let tx_createPool_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
     assert!(
         tx_createPool_result.is_ok(),
         "Create transaction failed: {:?}",
@@ -87,18 +119,18 @@ fn test_successful_deposit() {
     );
 
     let (pool_pda, _bump_pool_pda) =
-        utils::get_utils::get_pool_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
     let account_pool = svm.get_account(&pool_pda).expect("Should exist");
 
     let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
 
     println!("Pool exists: {}", svm.get_account(&pool_pda).is_some());
-    assert_eq!(data.liquidity, 0);
+    assert_eq!(data.liquidity, utils::get_utils::MINIMUM_LIQUIDITY);
     assert_eq!(data.token0, token0);
     assert_eq!(data.token1, token1);
 
     let (tokenliq_pda, tokenliq_bump) =
-        utils::get_utils::get_tokenliq_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
 
     let depositor_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
         .owner(&depositor.pubkey())
@@ -143,15 +175,18 @@ fn test_successful_deposit() {
 
     let amount_a = 10_000_000;
     let amount_b = 10_000_000;
+    let min_liquidity_out = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap()
+        - utils::get_utils::MINIMUM_LIQUIDITY;
     let tx_deposit_result = utils::handlers::handle_deposit(
         &mut svm,
         &program_id,
-        &admin,
+        &amm_config,
         &depositor,
         &token0,
         &token1,
         &amount_a,
         &amount_b,
+        &min_liquidity_out,
     );
     let account_ata = svm
         .get_account(&depositor_tokenliq_ata_account)
@@ -191,9 +226,24 @@ fn test_successful_deposit() {
 
     let expected_liquidity = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b);
 
-    assert_eq!(expected_liquidity.unwrap(), amount);
+    // MINIMUM_LIQUIDITY of the first deposit stays locked in the pool
+    assert_eq!(
+        expected_liquidity.unwrap() - utils::get_utils::MINIMUM_LIQUIDITY,
+        amount
+    );
     assert_eq!(expected_token0_after_deposit, depositor_token0_amount);
     assert_eq!(expected_token1_after_deposit, depositor_token1_amount);
+
+    let events: Vec<LiquidityAdded> =
+        utils::get_utils::get_events(&tx_deposit_result.unwrap().logs, "LiquidityAdded");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].depositor, depositor.pubkey());
+    assert_eq!(events[0].amount0, amount_a);
+    assert_eq!(events[0].amount1, amount_b);
+    assert_eq!(events[0].liquidity_minted, amount);
+    assert_eq!(events[0].reserve0, amount_a);
+    assert_eq!(events[0].reserve1, amount_b);
 }
 
 #[test]
@@ -206,7 +256,7 @@ fn test_revert_initialization_attack() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
     svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
@@ -247,11 +297,21 @@ fn test_revert_initialization_attack() {
             .unwrap();
     }
 
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
     let ix_createPool = utils::build_utils::build_createPool_instruction(
         &program_id,
         &admin.pubkey(),
         &token0,
         &token1,
+        &spl_token::id(),
+        &spl_token::id(),
+        &false,
+        &false,
+        &true,
+        &admin.pubkey(),
+        &fee_bps,
     );
     let tx_createPool = Transaction::new_signed_with_payer(
         &[ix_createPool],
@@ -268,17 +328,17 @@ fn test_revert_initialization_attack() {
     );
 
     let (pool_pda, _bump_pool_pda) =
-        utils::get_utils::get_pool_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
     let account_pool = svm.get_account(&pool_pda).expect("Should exist");
 
     let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
 
-    assert_eq!(data.liquidity, 0);
+    assert_eq!(data.liquidity, utils::get_utils::MINIMUM_LIQUIDITY);
     assert_eq!(data.token0, token0);
     assert_eq!(data.token1, token1);
 
     let (tokenliq_pda, tokenliq_bump) =
-        utils::get_utils::get_tokenliq_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
 
     let depositor_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
         .owner(&depositor.pubkey())
@@ -329,8 +389,11 @@ fn test_revert_initialization_attack() {
         &depositor.pubkey(),
         &token0,
         &token1,
+        &spl_token::id(),
+        &spl_token::id(),
         &amount_a,
         &amount_b,
+        &0,
     );
     let tx_deposit = Transaction::new_signed_with_payer(
         &[ix_deposit],
@@ -379,7 +442,408 @@ fn test_revert_initialization_attack() {
 
     let expected_liquidity = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b);
 
-    assert_eq!(expected_liquidity.unwrap(), amount);
+    // MINIMUM_LIQUIDITY of the first deposit stays locked in the pool
+    assert_eq!(
+        expected_liquidity.unwrap() - utils::get_utils::MINIMUM_LIQUIDITY,
+        amount
+    );
     assert_eq!(expected_token0_after_deposit, depositor_token0_amount);
     assert_eq!(expected_token1_after_deposit, depositor_token1_amount);
 }
+
+#[test]
+fn test_deposit_slippage_exceeded() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    // Requesting more LP than sqrt(a*b) must revert
+    let min_liquidity_out = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap()
+        - utils::get_utils::MINIMUM_LIQUIDITY
+        + 1;
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &min_liquidity_out,
+    );
+    match tx_deposit_result.unwrap_err().err {
+        // CustomError::InsufficientLiquidityOut
+        TransactionError::InstructionError(0, InstructionError::Custom(6007)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let depositor_ata_token0_address = get_associated_token_address(&depositor.pubkey(), &token0);
+    let depositor_ata_token0 = svm
+        .get_account(&depositor_ata_token0_address)
+        .expect("Should exist");
+    let depositor_token0_amount = Account::unpack(&depositor_ata_token0.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(depositor_token0_amount, amount_a);
+}
+
+#[test]
+fn test_proportional_deposit() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 20_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let supply = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap();
+
+    let depositor_ata_token0_address = get_associated_token_address(&depositor.pubkey(), &token0);
+    let depositor_ata_token1_address = get_associated_token_address(&depositor.pubkey(), &token1);
+
+    let max_amount_a = 1_000_000;
+    let max_amount_b = 1_000_000;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token0,
+        &depositor_ata_token0_address,
+        max_amount_a,
+    )
+    .send()
+    .unwrap();
+
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token1,
+        &depositor_ata_token1_address,
+        max_amount_b,
+    )
+    .send()
+    .unwrap();
+
+    // The pool holds twice as much token1, so token1 is the binding side
+    let expected_amount_a = max_amount_b * amount_a / amount_b;
+    let expected_amount_b = max_amount_b;
+    let expected_liquidity = std::cmp::min(
+        expected_amount_a * supply / amount_a,
+        expected_amount_b * supply / amount_b,
+    );
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &max_amount_a,
+        &max_amount_b,
+        &expected_liquidity,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let depositor_ata_token0 = svm
+        .get_account(&depositor_ata_token0_address)
+        .expect("Should exist");
+    let depositor_token0_amount = Account::unpack(&depositor_ata_token0.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    let depositor_ata_token1 = svm
+        .get_account(&depositor_ata_token1_address)
+        .expect("Should exist");
+    let depositor_token1_amount = Account::unpack(&depositor_ata_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    let (tokenliq_pda, _tokenliq_bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let depositor_tokenliq_ata = svm
+        .get_account(&get_associated_token_address(
+            &depositor.pubkey(),
+            &tokenliq_pda,
+        ))
+        .expect("Should exist");
+    let depositor_liquidity = Account::unpack(&depositor_tokenliq_ata.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(depositor_token0_amount, max_amount_a - expected_amount_a);
+    assert_eq!(depositor_token1_amount, max_amount_b - expected_amount_b);
+    assert_eq!(
+        depositor_liquidity,
+        supply - utils::get_utils::MINIMUM_LIQUIDITY + expected_liquidity
+    );
+}
+
+#[test]
+fn test_deposit_different_decimals() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    // SOL/USDC-like pair: 100 tokens with 9 decimals against 10_000 tokens with 6 decimals
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let decimals0 = 9;
+    let decimals1 = 6;
+    let amount_a = 100_000_000_000;
+    let amount_b = 10_000_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+        &decimals0,
+        &decimals1,
+        &spl_token::id(),
+        &spl_token::id(),
+    );
+
+    let expected_liquidity = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b)
+        .unwrap()
+        - utils::get_utils::MINIMUM_LIQUIDITY;
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &expected_liquidity,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let (tokenliq_pda, _tokenliq_bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let tokenliq_account = svm.get_account(&tokenliq_pda).expect("Should exist");
+    let tokenliq_decimals = Mint::unpack(&tokenliq_account.data)
+        .expect("failed to unpack mint")
+        .decimals;
+
+    let depositor_tokenliq_ata = svm
+        .get_account(&get_associated_token_address(
+            &depositor.pubkey(),
+            &tokenliq_pda,
+        ))
+        .expect("Should exist");
+    let depositor_liquidity = Account::unpack(&depositor_tokenliq_ata.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(tokenliq_decimals, (decimals0 + decimals1) / 2);
+    assert_eq!(depositor_liquidity, expected_liquidity);
+}
+
+#[test]
+fn test_deposit_transfer_fee_mints() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    // both mints withhold 1% of every transfer
+    let transfer_fee_bps = 100;
+    let maximum_fee = u64::MAX;
+    let mint_a = utils::setup_utils::create_transfer_fee_mint(
+        &mut svm,
+        &admin,
+        &admin.pubkey(),
+        &DECIMALS,
+        &transfer_fee_bps,
+        &maximum_fee,
+    );
+    let mint_b = utils::setup_utils::create_transfer_fee_mint(
+        &mut svm,
+        &admin,
+        &admin.pubkey(),
+        &DECIMALS,
+        &transfer_fee_bps,
+        &maximum_fee,
+    );
+    let (token0, token1) = if mint_a < mint_b {
+        (mint_a, mint_b)
+    } else {
+        (mint_b, mint_a)
+    };
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 20_000_000;
+    utils::setup_utils::setup_pool_for_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+        &token0,
+        &token1,
+    );
+
+    // LP is priced on what reaches the vaults, not on what the depositor sent
+    let received_a = amount_a
+        - utils::get_utils::get_expected_transfer_fee(&amount_a, &transfer_fee_bps, &maximum_fee);
+    let received_b = amount_b
+        - utils::get_utils::get_expected_transfer_fee(&amount_b, &transfer_fee_bps, &maximum_fee);
+    let expected_liquidity = utils::get_utils::get_expected_liquidity(&received_a, &received_b)
+        .unwrap()
+        - utils::get_utils::MINIMUM_LIQUIDITY;
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &expected_liquidity,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let (pool_pda, _pool_bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let (pool_authority_pda, _pool_authority_bump) =
+        utils::get_utils::get_pool_authority_pda(&program_id, &pool_pda, &token0, &token1);
+    let token0_vault = svm
+        .get_account(&get_associated_token_address_with_program_id(
+            &pool_authority_pda,
+            &token0,
+            &spl_token_2022_interface::id(),
+        ))
+        .expect("Should exist");
+    let token1_vault = svm
+        .get_account(&get_associated_token_address_with_program_id(
+            &pool_authority_pda,
+            &token1,
+            &spl_token_2022_interface::id(),
+        ))
+        .expect("Should exist");
+
+    let (tokenliq_pda, _tokenliq_bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let depositor_tokenliq_ata = svm
+        .get_account(&get_associated_token_address(
+            &depositor.pubkey(),
+            &tokenliq_pda,
+        ))
+        .expect("Should exist");
+
+    assert_eq!(
+        utils::get_utils::get_token_balance(&token0_vault.data),
+        received_a
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&token1_vault.data),
+        received_b
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&depositor_tokenliq_ata.data),
+        expected_liquidity
+    );
+}
@@ -0,0 +1,286 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::spl_token,
+    solana_sdk::{
+        instruction::{Instruction, InstructionError},
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize, PartialEq, Eq)]
+enum PoolStatus {
+    Active,
+    Paused,
+    Delisted,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct Pool {
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub liquidity: u64,
+    pub fee_bps: u16,
+    pub protocol_fees_token0: u64,
+    pub protocol_fees_token1: u64,
+    pub status: PoolStatus,
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    pub last_update_timestamp: i64,
+    pub flash_loan_active: bool,
+    pub flash_loan_amount0: u64,
+    pub flash_loan_amount1: u64,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct FlashLoanRepaid {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub fee0: u64,
+    pub fee1: u64,
+}
+
+#[test]
+fn test_flash_loan() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let amount_a = 1_000_000;
+    let amount_b = 1_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // alice only holds enough to pay the fees
+    let alice_funds = 1_000;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_funds);
+
+    let borrow_amount0 = 100_000;
+    let borrow_amount1 = 50_000;
+    let borrow_ix = utils::build_utils::build_flashBorrow_instruction(
+        &program_id,
+        &amm_config,
+        &alice.pubkey(),
+        &token0,
+        &token1,
+        &spl_token::id(),
+        &spl_token::id(),
+        &borrow_amount0,
+        &borrow_amount1,
+    );
+    let repay_ix = utils::build_utils::build_flashRepay_instruction(
+        &program_id,
+        &amm_config,
+        &alice.pubkey(),
+        &token0,
+        &token1,
+        &spl_token::id(),
+        &spl_token::id(),
+    );
+
+    // a borrow without a later repay is refused
+    let tx_result = helper_send(&mut svm, &alice, &[borrow_ix.clone()]);
+    match tx_result.unwrap_err().err {
+        // CustomError::FlashLoanNotRepaid
+        TransactionError::InstructionError(0, InstructionError::Custom(6026)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // so is a repay before the borrow
+    let tx_result = helper_send(&mut svm, &alice, &[repay_ix.clone(), borrow_ix.clone()]);
+    match tx_result.unwrap_err().err {
+        // CustomError::FlashLoanNotActive
+        TransactionError::InstructionError(0, InstructionError::Custom(6025)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // the pool is locked while the loan is open
+    let swap_ix = utils::build_utils::build_swapExactInput_instruction(
+        &program_id,
+        &amm_config,
+        &alice.pubkey(),
+        &token0,
+        &token1,
+        &spl_token::id(),
+        &spl_token::id(),
+        &token0,
+        &100,
+        &0,
+    );
+    let tx_result = utils::handlers::handle_flash_loan(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &borrow_amount0,
+        &borrow_amount1,
+        &[swap_ix],
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::FlashLoanActive
+        TransactionError::InstructionError(1, InstructionError::Custom(6024)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_flash_loan(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &borrow_amount0,
+        &borrow_amount1,
+        &[borrow_ix],
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::FlashLoanActive
+        TransactionError::InstructionError(1, InstructionError::Custom(6024)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_flash_loan(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &borrow_amount0,
+        &borrow_amount1,
+        &[],
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Flash loan transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let fee0 = utils::get_utils::get_expected_flash_loan_fee(&borrow_amount0, &fee_bps);
+    let fee1 = utils::get_utils::get_expected_flash_loan_fee(&borrow_amount1, &fee_bps);
+    assert_eq!(fee0, 300);
+    assert_eq!(fee1, 150);
+
+    let events: Vec<FlashLoanRepaid> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "FlashLoanRepaid");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].borrower, alice.pubkey());
+    assert_eq!(events[0].amount0, borrow_amount0);
+    assert_eq!(events[0].amount1, borrow_amount1);
+    assert_eq!(events[0].fee0, fee0);
+    assert_eq!(events[0].fee1, fee1);
+
+    let (pool_pda, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let account_pool = svm.get_account(&pool_pda).unwrap();
+    let pool = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
+    assert!(!pool.flash_loan_active);
+    assert_eq!(pool.flash_loan_amount0, 0);
+    assert_eq!(pool.flash_loan_amount1, 0);
+    // the fees stay in the reserves, none of it goes to the protocol
+    assert_eq!(pool.protocol_fees_token0, 0);
+    assert_eq!(pool.protocol_fees_token1, 0);
+
+    let (pool_authority, _bump) =
+        utils::get_utils::get_pool_authority_pda(&program_id, &pool_pda, &token0, &token1);
+    let vault0 = get_associated_token_address(&pool_authority, &token0);
+    let vault1 = get_associated_token_address(&pool_authority, &token1);
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&vault0).unwrap().data),
+        amount_a + fee0
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&vault1).unwrap().data),
+        amount_b + fee1
+    );
+
+    let alice_token0_ata = get_associated_token_address(&alice.pubkey(), &token0);
+    let alice_token1_ata = get_associated_token_address(&alice.pubkey(), &token1);
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token0_ata).unwrap().data),
+        alice_funds - fee0
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token1_ata).unwrap().data),
+        alice_funds - fee1
+    );
+
+    // and swaps go through again once it is repaid
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &100,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.err()
+    );
+}
+
+fn helper_send(
+    svm: &mut LiteSVM,
+    signer: &Keypair,
+    instructions: &[Instruction],
+) -> litesvm::types::TransactionResult {
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signer.pubkey()),
+        &[signer],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx)
+}
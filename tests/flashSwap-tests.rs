@@ -0,0 +1,201 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::spl_token::{self, native_mint::DECIMALS},
+    solana_sdk::{
+        instruction::{AccountMeta, InstructionError},
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct FlashSwapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub amount0_out: u64,
+    pub amount1_out: u64,
+    pub amount0_in: u64,
+    pub amount1_in: u64,
+    pub protocol_fee0: u64,
+    pub protocol_fee1: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[test]
+fn test_flash_swap() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let amount_a = 1_000_000;
+    let amount_b = 2_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let alice_funds = 100_000;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_funds);
+
+    let (pool_pda, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let (pool_authority, _bump) =
+        utils::get_utils::get_pool_authority_pda(&program_id, &pool_pda, &token0, &token1);
+    let vault0 = get_associated_token_address(&pool_authority, &token0);
+    let vault1 = get_associated_token_address(&pool_authority, &token1);
+    let alice_token0_ata = get_associated_token_address(&alice.pubkey(), &token0);
+    let alice_token1_ata = get_associated_token_address(&alice.pubkey(), &token1);
+
+    // token1 is taken first and paid for with token0 from inside the callback,
+    // here a plain token transfer from alice into the vault
+    let amount1_out = 10_000;
+    let amount0_in =
+        utils::get_utils::get_expected_amount_in(&amount1_out, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+    let callback_accounts = [
+        AccountMeta::new(alice_token0_ata, false),
+        AccountMeta::new_readonly(token0, false),
+        AccountMeta::new(vault0, false),
+        AccountMeta::new_readonly(alice.pubkey(), true),
+    ];
+
+    // the callback can't call back into the pool program
+    let tx_result = utils::handlers::handle_flash_swap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &0,
+        &amount1_out,
+        &program_id,
+        &[],
+        &[],
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidCallbackProgram
+        TransactionError::InstructionError(0, InstructionError::Custom(6028)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // one token short of the fee-adjusted invariant
+    let tx_result = utils::handlers::handle_flash_swap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &0,
+        &amount1_out,
+        &spl_token::id(),
+        &helper_transfer_checked_data(&(amount0_in - 1)),
+        &callback_accounts,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvariantViolated
+        TransactionError::InstructionError(0, InstructionError::Custom(6027)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_flash_swap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &0,
+        &amount1_out,
+        &spl_token::id(),
+        &helper_transfer_checked_data(&amount0_in),
+        &callback_accounts,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Flash swap transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<FlashSwapped> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "FlashSwapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].swapper, alice.pubkey());
+    assert_eq!(events[0].amount0_out, 0);
+    assert_eq!(events[0].amount1_out, amount1_out);
+    assert_eq!(events[0].amount0_in, amount0_in);
+    assert_eq!(events[0].amount1_in, 0);
+    assert_eq!(
+        events[0].reserve0,
+        amount_a + amount0_in - events[0].protocol_fee0
+    );
+    assert_eq!(events[0].reserve1, amount_b - amount1_out);
+
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token0_ata).unwrap().data),
+        alice_funds - amount0_in
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token1_ata).unwrap().data),
+        alice_funds + amount1_out
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&vault0).unwrap().data),
+        amount_a + amount0_in
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&vault1).unwrap().data),
+        amount_b - amount1_out
+    );
+}
+
+// spl-token TransferChecked of `amount` with the default mint decimals
+fn helper_transfer_checked_data(amount: &u64) -> Vec<u8> {
+    let mut data = vec![12];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(DECIMALS);
+    data
+}
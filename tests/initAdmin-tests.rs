@@ -2,7 +2,7 @@ use {
     borsh::BorshDeserialize,
     litesvm::LiteSVM,
     solana_sdk::{
-        instruction::{AccountMeta, Instruction},
+        instruction::{AccountMeta, Instruction, InstructionError},
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
         transaction::{Transaction, TransactionError},
@@ -16,6 +16,11 @@ struct AdminSettings {
     pub admin: Pubkey,
 }
 
+#[derive(Debug, BorshDeserialize)]
+struct AdminInitialized {
+    pub admin: Pubkey,
+}
+
 #[test]
 fn test_init_admin() {
     let mut svm = LiteSVM::new();
@@ -24,7 +29,7 @@ fn test_init_admin() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
 
@@ -32,7 +37,7 @@ fn test_init_admin() {
     assert!(
         tx_result.is_ok(),
         "Create transaction failed: {:?}",
-        tx_result.err()
+        tx_result.as_ref().err()
     );
 
     let (admin_settings, _bump) = utils::get_utils::get_admin_settings_pda(&program_id);
@@ -42,6 +47,11 @@ fn test_init_admin() {
         .expect("Failed to deserialize AdminSettings");
 
     assert_eq!(data.admin, admin.pubkey(), "Owner mismatch");
+
+    let events: Vec<AdminInitialized> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "AdminInitialized");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].admin, admin.pubkey());
 }
 
 #[test]
@@ -53,7 +63,7 @@ fn test_already_initialized() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
     svm.airdrop(&user.pubkey(), 10_000_000).unwrap();
@@ -84,3 +94,36 @@ fn test_already_initialized() {
         _ => panic!("Got unexpected error: {:?}", err.err),
     }
 }
+
+#[test]
+fn test_init_admin_not_upgrade_authority() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let user = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
+    svm.airdrop(&user.pubkey(), 10_000_000).unwrap();
+
+    // user races the deployer to init_admin but doesn't hold the upgrade authority
+    let tx_result_user = utils::handlers::handle_init_admin(&mut svm, &program_id, &user);
+    match tx_result_user.unwrap_err().err {
+        // CustomError::Unauthorized
+        TransactionError::InstructionError(0, InstructionError::Custom(6000)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let (admin_settings, _bump) = utils::get_utils::get_admin_settings_pda(&program_id);
+    assert!(svm.get_account(&admin_settings).is_none());
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+}
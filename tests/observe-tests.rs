@@ -0,0 +1,495 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::MintTo,
+    solana_sdk::{
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct PriceObservation {
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    pub timestamp: i64,
+}
+
+#[test]
+fn test_observe_twap() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    // token1 trades at 4 per token0
+    let amount_a = 1_000_000;
+    let amount_b = 4_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+    let start = svm.get_sysvar::<Clock>().unix_timestamp;
+
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    helper_warp_seconds(&mut svm, 100);
+    let first = helper_observe(&mut svm, &program_id, &amm_config, &alice, &token0, &token1);
+
+    // the empty pool before the deposit has no price and adds nothing
+    assert_eq!(first.timestamp, start + 100);
+    assert_eq!(first.price0_cumulative, (4u128 << 64) * 100);
+    assert_eq!(first.price1_cumulative, (1u128 << 62) * 100);
+
+    let swap_amount = 100_000;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &swap_amount);
+    let amount_out =
+        utils::get_utils::get_expected_amount_out(&swap_amount, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &swap_amount,
+        &amount_out,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    helper_warp_seconds(&mut svm, 50);
+    svm.expire_blockhash();
+    let second = helper_observe(&mut svm, &program_id, &amm_config, &alice, &token0, &token1);
+
+    // the swap only moves the price from its own timestamp on, so the TWAP
+    // over the last 50 seconds is the post-swap spot price
+    let reserve0 = (amount_a + swap_amount) as u128;
+    let reserve1 = (amount_b - amount_out) as u128;
+    let elapsed = (second.timestamp - first.timestamp) as u128;
+    assert_eq!(elapsed, 50);
+    assert_eq!(
+        second
+            .price0_cumulative
+            .wrapping_sub(first.price0_cumulative)
+            / elapsed,
+        (reserve1 << 64) / reserve0
+    );
+    assert_eq!(
+        second
+            .price1_cumulative
+            .wrapping_sub(first.price1_cumulative)
+            / elapsed,
+        (reserve0 << 64) / reserve1
+    );
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct Twap {
+    pub price0: u128,
+    pub price1: u128,
+    pub liquidity: u128,
+}
+
+#[test]
+fn test_grow_observations_and_get_twap() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let amount_a = 1_000_000;
+    let amount_b = 4_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+    let start = svm.get_sysvar::<Clock>().unix_timestamp;
+
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // the pool starts with room for a single observation
+    let tx_result = utils::handlers::handle_grow_observations(
+        &mut svm,
+        &program_id,
+        &alice,
+        &amm_config,
+        &token0,
+        &token1,
+        &1,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidObservationCapacity
+        TransactionError::InstructionError(0, InstructionError::Custom(6020)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let capacity = 10;
+    let tx_result = utils::handlers::handle_grow_observations(
+        &mut svm,
+        &program_id,
+        &alice,
+        &amm_config,
+        &token0,
+        &token1,
+        &capacity,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Grow observations transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let (pool_pda, _bump_pool) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let (observations, _bump_observations) =
+        utils::get_utils::get_observations_pda(&program_id, &pool_pda);
+    let account_observations = svm.get_account(&observations).expect("Should exist");
+    // discriminator, pool, index, cardinality, vec length, 57 bytes per observation
+    assert_eq!(
+        account_observations.data.len(),
+        8 + 32 + 2 + 2 + 4 + 57 * capacity as usize
+    );
+
+    // two swaps 100 seconds apart, each recorded as an observation
+    let swap_amount = 100_000;
+    utils::setup_utils::fund_swapper(
+        &mut svm,
+        &admin,
+        &alice,
+        &token0,
+        &token1,
+        &(2 * swap_amount),
+    );
+
+    helper_warp_seconds(&mut svm, 100);
+    let amount_out_1 =
+        utils::get_utils::get_expected_amount_out(&swap_amount, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &swap_amount,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.err()
+    );
+    let reserve0_1 = amount_a + swap_amount;
+    let reserve1_1 = amount_b - amount_out_1;
+
+    helper_warp_seconds(&mut svm, 100);
+    svm.expire_blockhash();
+    let amount_out_2 =
+        utils::get_utils::get_expected_amount_out(&swap_amount, &reserve0_1, &reserve1_1, &fee_bps)
+            .unwrap();
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &swap_amount,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.err()
+    );
+    let reserve0_2 = reserve0_1 + swap_amount;
+    let reserve1_2 = reserve1_1 - amount_out_2;
+
+    helper_warp_seconds(&mut svm, 50);
+
+    let initial_price = 4u128 << 64;
+    let price_1 = ((reserve1_1 as u128) << 64) / reserve0_1 as u128;
+    let price_2 = ((reserve1_2 as u128) << 64) / reserve0_2 as u128;
+
+    // half the window at the initial price, half after the first swap
+    let twap = helper_get_twap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        start + 50,
+        start + 150,
+    );
+    assert_eq!(twap.price0, (initial_price * 50 + price_1 * 50) / 100);
+    assert_eq!(twap.liquidity, 2_000_000);
+
+    // a window running up to now is carried forward from the newest observation
+    let twap = helper_get_twap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        start + 200,
+        start + 250,
+    );
+    assert_eq!(twap.price0, price_2);
+    assert_eq!(
+        twap.price1,
+        ((reserve0_2 as u128) << 64) / reserve1_2 as u128
+    );
+
+    let tx_result = utils::handlers::handle_get_twap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &(start - 1),
+        &(start + 10),
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::ObservationTooOld
+        TransactionError::InstructionError(0, InstructionError::Custom(6021)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_get_twap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &(start + 200),
+        &(start + 300),
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidTimeWindow
+        TransactionError::InstructionError(0, InstructionError::Custom(6022)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn test_get_twap_ignores_vault_donation() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let amount_a = 1_000_000;
+    let amount_b = 4_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+    let start = svm.get_sysvar::<Clock>().unix_timestamp;
+
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // a plain transfer into the vault that would take the spot price from 4 to 1
+    helper_warp_seconds(&mut svm, 100);
+    let (pool_pda, _bump_pool) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let (pool_authority, _bump_pool_authority) =
+        utils::get_utils::get_pool_authority_pda(&program_id, &pool_pda, &token0, &token1);
+    let token0_vault = get_associated_token_address(&pool_authority, &token0);
+    MintTo::new(&mut svm, &admin, &token0, &token0_vault, 3 * amount_a)
+        .send()
+        .unwrap();
+
+    helper_warp_seconds(&mut svm, 100);
+    svm.expire_blockhash();
+
+    // no instruction has synced the donation, so the price never moved
+    let twap = helper_get_twap(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        start,
+        start + 200,
+    );
+    assert_eq!(twap.price0, 4u128 << 64);
+    assert_eq!(twap.price1, 1u128 << 62);
+
+    let observation = helper_observe(&mut svm, &program_id, &amm_config, &alice, &token0, &token1);
+    assert_eq!(observation.price0_cumulative, (4u128 << 64) * 200);
+}
+
+fn helper_warp_seconds(svm: &mut LiteSVM, seconds: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += seconds;
+    svm.set_sysvar::<Clock>(&clock);
+}
+
+fn helper_observe(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    payer: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> PriceObservation {
+    let tx_result =
+        utils::handlers::handle_observe(svm, program_id, amm_config, payer, token0, token1);
+    assert!(
+        tx_result.is_ok(),
+        "Observe transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    PriceObservation::deserialize(&mut &tx_result.unwrap().return_data.data[..])
+        .expect("Failed to deserialize PriceObservation")
+}
+
+fn helper_get_twap(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    payer: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    start_timestamp: i64,
+    end_timestamp: i64,
+) -> Twap {
+    let tx_result = utils::handlers::handle_get_twap(
+        svm,
+        program_id,
+        amm_config,
+        payer,
+        token0,
+        token1,
+        &start_timestamp,
+        &end_timestamp,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Get TWAP transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    Twap::deserialize(&mut &tx_result.unwrap().return_data.data[..])
+        .expect("Failed to deserialize Twap")
+}
@@ -0,0 +1,184 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct RoleAssignment {
+    pub holder: Pubkey,
+    pub roles: u8,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct AdminSettings {
+    pub admin: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub paused: bool,
+    pub pending_admin: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct RoleGranted {
+    pub holder: Pubkey,
+    pub roles: u8,
+    pub holder_roles: u8,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct RoleRevoked {
+    pub holder: Pubkey,
+    pub roles: u8,
+    pub holder_roles: u8,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct GlobalPauseChanged {
+    pub pauser: Pubkey,
+    pub paused: bool,
+}
+
+#[test]
+fn test_admin_holds_all_roles() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let (admin_role, _bump) = utils::get_utils::get_role_pda(&program_id, &admin.pubkey());
+    let account_admin_role = svm.get_account(&admin_role).expect("Should exist");
+
+    let data = RoleAssignment::deserialize(&mut &account_admin_role.data[8..])
+        .expect("Failed to deserialize RoleAssignment");
+
+    assert_eq!(data.holder, admin.pubkey());
+    assert_eq!(
+        data.roles,
+        utils::get_utils::POOL_CREATOR
+            | utils::get_utils::FEE_MANAGER
+            | utils::get_utils::PAUSER
+            | utils::get_utils::TREASURY_WITHDRAWER
+    );
+}
+
+#[test]
+fn test_grant_and_revoke_role() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let pauser = Keypair::new();
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
+    svm.airdrop(&pauser.pubkey(), 10_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // only the admin hands out roles
+    let tx_result = utils::handlers::handle_grant_role(
+        &mut svm,
+        &program_id,
+        &pauser,
+        &pauser.pubkey(),
+        &utils::get_utils::PAUSER,
+    );
+    assert!(tx_result.is_err());
+
+    let tx_result = utils::handlers::handle_grant_role(
+        &mut svm,
+        &program_id,
+        &admin,
+        &pauser.pubkey(),
+        &utils::get_utils::PAUSER,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Grant role transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<RoleGranted> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "RoleGranted");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].holder, pauser.pubkey());
+    assert_eq!(events[0].roles, utils::get_utils::PAUSER);
+    assert_eq!(events[0].holder_roles, utils::get_utils::PAUSER);
+
+    let tx_result = utils::handlers::handle_set_global_pause(&mut svm, &program_id, &pauser, &true);
+    assert!(
+        tx_result.is_ok(),
+        "Set global pause transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<GlobalPauseChanged> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "GlobalPauseChanged");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pauser, pauser.pubkey());
+    assert!(events[0].paused);
+
+    let (admin_settings, _bump) = utils::get_utils::get_admin_settings_pda(&program_id);
+    let account_admin_settings = svm.get_account(&admin_settings).expect("Should exist");
+    let data = AdminSettings::deserialize(&mut &account_admin_settings.data[8..])
+        .expect("Failed to deserialize AdminSettings");
+    assert!(data.paused);
+
+    // pausing doesn't make the holder a fee manager
+    let tx_result = utils::handlers::handle_set_protocol_fee(&mut svm, &program_id, &pauser, &100);
+    match tx_result.unwrap_err().err {
+        // CustomError::MissingRole
+        TransactionError::InstructionError(0, InstructionError::Custom(6016)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_revoke_role(
+        &mut svm,
+        &program_id,
+        &admin,
+        &pauser.pubkey(),
+        &utils::get_utils::PAUSER,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Revoke role transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<RoleRevoked> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "RoleRevoked");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].holder, pauser.pubkey());
+    assert_eq!(events[0].roles, utils::get_utils::PAUSER);
+    assert_eq!(events[0].holder_roles, 0);
+
+    let tx_result =
+        utils::handlers::handle_set_global_pause(&mut svm, &program_id, &pauser, &false);
+    assert!(tx_result.is_err());
+}
@@ -1,8 +1,9 @@
 use {
     borsh::BorshDeserialize,
     litesvm::LiteSVM,
+    litesvm_token::CreateAssociatedTokenAccount,
     solana_sdk::{
-        instruction::{AccountMeta, Instruction},
+        instruction::{AccountMeta, Instruction, InstructionError},
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
         transaction::{Transaction, TransactionError},
@@ -14,6 +15,21 @@ mod utils;
 #[derive(Debug, BorshDeserialize)]
 struct AdminSettings {
     pub admin: Pubkey,
+    pub protocol_fee_bps: u16,
+    pub paused: bool,
+    pub pending_admin: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct AdminAccepted {
+    pub previous_admin: Pubkey,
+    pub admin: Pubkey,
 }
 
 #[test]
@@ -25,7 +41,7 @@ fn test_set_admin() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
     svm.airdrop(&new_admin.pubkey(), 10_000_000).unwrap();
@@ -41,22 +57,53 @@ fn test_set_admin() {
 
     // Then we check for actual set
     let tx_result_set =
-        utils::handlers::handle_set_admin(&mut svm, &program_id, &new_admin, &admin);
+        utils::handlers::handle_propose_admin(&mut svm, &program_id, &new_admin, &admin);
     assert!(
         tx_result_set.is_ok(),
-        "Set admin tx failed: {:?}",
-        tx_result_set.err()
+        "Propose admin tx failed: {:?}",
+        tx_result_set.as_ref().err()
     );
 
+    let events: Vec<AdminProposed> =
+        utils::get_utils::get_events(&tx_result_set.unwrap().logs, "AdminProposed");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].admin, admin.pubkey());
+    assert_eq!(events[0].pending_admin, new_admin.pubkey());
+
     let (admin_settings, _bump) = utils::get_utils::get_admin_settings_pda(&program_id);
     let account_admin_settings = svm.get_account(&admin_settings).expect("Should exist");
 
     let data = AdminSettings::deserialize(&mut &account_admin_settings.data[8..])
         .expect("Failed to deserialize AdminSettings");
 
-    println!("{}", new_admin.pubkey());
-    println!("{}", admin.pubkey());
+    // nothing changes until the new admin accepts
+    assert_eq!(data.admin, admin.pubkey(), "Owner mismatch");
+    assert_eq!(
+        data.pending_admin,
+        new_admin.pubkey(),
+        "Pending admin mismatch"
+    );
+
+    let tx_result_accept = utils::handlers::handle_accept_admin(&mut svm, &program_id, &new_admin);
+    assert!(
+        tx_result_accept.is_ok(),
+        "Accept admin tx failed: {:?}",
+        tx_result_accept.as_ref().err()
+    );
+
+    let events: Vec<AdminAccepted> =
+        utils::get_utils::get_events(&tx_result_accept.unwrap().logs, "AdminAccepted");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].previous_admin, admin.pubkey());
+    assert_eq!(events[0].admin, new_admin.pubkey());
+
+    let account_admin_settings = svm.get_account(&admin_settings).expect("Should exist");
+
+    let data = AdminSettings::deserialize(&mut &account_admin_settings.data[8..])
+        .expect("Failed to deserialize AdminSettings");
+
     assert_eq!(data.admin, new_admin.pubkey(), "Owner mismatch");
+    assert_eq!(data.pending_admin, Pubkey::default());
 }
 
 #[test]
@@ -69,7 +116,7 @@ fn test_unauthorized_set() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
     svm.airdrop(&user.pubkey(), 10_000_000).unwrap();
@@ -82,6 +129,165 @@ fn test_unauthorized_set() {
     );
 
     // Then we check for actual set
-    let tx_result_set = utils::handlers::handle_set_admin(&mut svm, &program_id, &user, &user);
+    let tx_result_set = utils::handlers::handle_propose_admin(&mut svm, &program_id, &user, &user);
     assert!(tx_result_set.is_err());
+
+    // a key that was never proposed can't take over either
+    let tx_result_accept = utils::handlers::handle_accept_admin(&mut svm, &program_id, &user);
+    assert!(tx_result_accept.is_err());
+}
+
+#[test]
+fn test_cancel_admin_proposal() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let new_admin = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000).unwrap();
+    svm.airdrop(&new_admin.pubkey(), 10_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let tx_result_set =
+        utils::handlers::handle_propose_admin(&mut svm, &program_id, &new_admin, &admin);
+    assert!(
+        tx_result_set.is_ok(),
+        "Propose admin tx failed: {:?}",
+        tx_result_set.err()
+    );
+
+    // only the current admin can withdraw the proposal
+    let tx_result_cancel =
+        utils::handlers::handle_cancel_admin_proposal(&mut svm, &program_id, &new_admin);
+    assert!(tx_result_cancel.is_err());
+
+    let tx_result_cancel =
+        utils::handlers::handle_cancel_admin_proposal(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result_cancel.is_ok(),
+        "Cancel admin proposal tx failed: {:?}",
+        tx_result_cancel.err()
+    );
+
+    let tx_result_accept = utils::handlers::handle_accept_admin(&mut svm, &program_id, &new_admin);
+    assert!(tx_result_accept.is_err());
+
+    let (admin_settings, _bump) = utils::get_utils::get_admin_settings_pda(&program_id);
+    let account_admin_settings = svm.get_account(&admin_settings).expect("Should exist");
+
+    let data = AdminSettings::deserialize(&mut &account_admin_settings.data[8..])
+        .expect("Failed to deserialize AdminSettings");
+
+    assert_eq!(data.admin, admin.pubkey(), "Owner mismatch");
+}
+
+#[test]
+fn test_admin_handover_moves_roles() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let new_admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1_000_000_000).unwrap();
+    svm.airdrop(&new_admin.pubkey(), 10_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000).unwrap();
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &1_000_000,
+        &1_000_000,
+    );
+
+    let tx_result =
+        utils::handlers::handle_propose_admin(&mut svm, &program_id, &new_admin, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Propose admin tx failed: {:?}",
+        tx_result.err()
+    );
+    let tx_result = utils::handlers::handle_accept_admin(&mut svm, &program_id, &new_admin);
+    assert!(
+        tx_result.is_ok(),
+        "Accept admin tx failed: {:?}",
+        tx_result.err()
+    );
+
+    // the old key can neither pause pools nor collect fees
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &1,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MissingRole
+        TransactionError::InstructionError(0, InstructionError::Custom(6016)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let treasury_token0_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&admin.pubkey())
+        .send()
+        .unwrap();
+    let treasury_token1_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&admin.pubkey())
+        .send()
+        .unwrap();
+    let tx_result = utils::handlers::handle_collect_protocol_fees(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &treasury_token0_ata,
+        &treasury_token1_ata,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MissingRole
+        TransactionError::InstructionError(0, InstructionError::Custom(6016)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // while the new admin holds every role
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &new_admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &1,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Set pool status tx failed: {:?}",
+        tx_result.err()
+    );
 }
@@ -0,0 +1,271 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+};
+
+mod utils;
+
+const ACTIVE: u8 = 0;
+const PAUSED: u8 = 1;
+
+#[derive(Debug, BorshDeserialize)]
+struct Pool {
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub liquidity: u64,
+    pub fee_bps: u16,
+    pub protocol_fees_token0: u64,
+    pub protocol_fees_token1: u64,
+    pub status: u8,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct PoolStatusChanged {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    pub status: u8,
+}
+
+#[test]
+fn test_paused_pool_only_allows_withdrawals() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &alice,
+        &amm_config,
+        &token0,
+        &token1,
+        &PAUSED,
+    );
+    assert!(tx_result.is_err());
+
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &PAUSED,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Set pool status transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let (pool_pda, _bump_pool_pda) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let events: Vec<PoolStatusChanged> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "PoolStatusChanged");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].authority, admin.pubkey());
+    assert_eq!(events[0].status, PAUSED);
+    let account_pool = svm.get_account(&pool_pda).expect("Should exist");
+    let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
+    assert_eq!(data.status, PAUSED);
+
+    let alice_amount_a = 100;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_amount_a);
+
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &0,
+    );
+    match tx_swap_result.unwrap_err().err {
+        // CustomError::PoolPaused
+        TransactionError::InstructionError(0, InstructionError::Custom(6014)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(tx_deposit_result.is_err());
+
+    // LPs can always leave
+    let withdraw_amount = 1_000;
+    let tx_withdraw_result = utils::handlers::handle_withdraw(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &withdraw_amount,
+        &0,
+        &0,
+    );
+    assert!(
+        tx_withdraw_result.is_ok(),
+        "Withdraw transaction failed: {:?}",
+        tx_withdraw_result.err()
+    );
+
+    let tx_result = utils::handlers::handle_set_pool_status(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &ACTIVE,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Set pool status transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    svm.expire_blockhash();
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &0,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+}
+
+#[test]
+fn test_global_pause() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let tx_result = utils::handlers::handle_set_global_pause(&mut svm, &program_id, &alice, &true);
+    assert!(tx_result.is_err());
+
+    let tx_result = utils::handlers::handle_set_global_pause(&mut svm, &program_id, &admin, &true);
+    assert!(
+        tx_result.is_ok(),
+        "Set global pause transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let alice_amount_a = 100;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_amount_a);
+
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &0,
+    );
+    match tx_swap_result.unwrap_err().err {
+        // CustomError::ProtocolPaused
+        TransactionError::InstructionError(0, InstructionError::Custom(6015)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let withdraw_amount = 1_000;
+    let tx_withdraw_result = utils::handlers::handle_withdraw(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &withdraw_amount,
+        &0,
+        &0,
+    );
+    assert!(
+        tx_withdraw_result.is_ok(),
+        "Withdraw transaction failed: {:?}",
+        tx_withdraw_result.err()
+    );
+}
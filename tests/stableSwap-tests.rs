@@ -0,0 +1,540 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::spl_token::native_mint::DECIMALS,
+    solana_sdk::{
+        clock::Clock,
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct Pool {
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub liquidity: u64,
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct Swapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub token_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub amount_out_received: u64,
+    pub swap_fee: u64,
+    pub protocol_fee: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct PriceObservation {
+    pub price0_cumulative: u128,
+    pub price1_cumulative: u128,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct AmpRampStarted {
+    pub pool: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_ramp_start: i64,
+    pub amp_ramp_end: i64,
+}
+
+#[test]
+fn test_stable_swap_pool() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let (token0, token1) = utils::setup_utils::create_mints(&mut svm, &admin, &DECIMALS, &DECIMALS);
+
+    let tx_result = utils::handlers::handle_create_stable_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+        &0,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidAmp
+        TransactionError::InstructionError(0, InstructionError::Custom(6029)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let amp = 100;
+    let tx_result = utils::handlers::handle_create_stable_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+        &amp,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create stable pool transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let amount_a = 1_000_000;
+    let amount_b = 1_000_000;
+    utils::setup_utils::fund_depositor(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &amm_config,
+        &amount_a,
+        &amount_b,
+        &token0,
+        &token1,
+    );
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // balanced reserves: D is their sum
+    let (pool_pda, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let account_pool = svm.get_account(&pool_pda).unwrap();
+    let pool = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
+    assert_eq!(pool.liquidity, amount_a + amount_b);
+
+    let (tokenliq_pda, _bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let depositor_lp_ata = get_associated_token_address(&depositor.pubkey(), &tokenliq_pda);
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&depositor_lp_ata).unwrap().data),
+        amount_a + amount_b - utils::get_utils::MINIMUM_LIQUIDITY
+    );
+
+    let alice_funds = 100_000;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_funds);
+
+    // near the peg the stable curve gives a better rate than x * y = k
+    let amount_in = 10_000;
+    let expected_amount_out = utils::get_utils::get_expected_stable_amount_out(
+        &amount_in, &amount_a, &amount_b, &fee_bps, &amp,
+    );
+    let constant_product_amount_out =
+        utils::get_utils::get_expected_amount_out(&amount_in, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+    assert!(expected_amount_out > constant_product_amount_out);
+
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &amount_in,
+        &expected_amount_out,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].amount_in, amount_in);
+    assert_eq!(events[0].amount_out, expected_amount_out);
+    assert_eq!(events[0].reserve0, amount_a + amount_in);
+    assert_eq!(events[0].reserve1, amount_b - expected_amount_out);
+    let (reserve0, reserve1) = (events[0].reserve0, events[0].reserve1);
+
+    // ramp_amp is for fee managers only
+    let tx_result = utils::handlers::handle_grant_role(
+        &mut svm,
+        &program_id,
+        &admin,
+        &alice.pubkey(),
+        &utils::get_utils::PAUSER,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Grant role transaction failed: {:?}",
+        tx_result.err()
+    );
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    let day = 86_400;
+    let tx_result = utils::handlers::handle_ramp_amp(
+        &mut svm,
+        &program_id,
+        &alice,
+        &amm_config,
+        &token0,
+        &token1,
+        &200,
+        &(now + 2 * day),
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::MissingRole
+        TransactionError::InstructionError(0, InstructionError::Custom(6016)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // shorter than a day, then more than a tenfold change
+    for (target_amp, ramp_end) in [(200, now + day - 1), (amp * 10 + 1, now + 2 * day)] {
+        let tx_result = utils::handlers::handle_ramp_amp(
+            &mut svm,
+            &program_id,
+            &admin,
+            &amm_config,
+            &token0,
+            &token1,
+            &target_amp,
+            &ramp_end,
+        );
+        match tx_result.unwrap_err().err {
+            // CustomError::InvalidAmpRamp
+            TransactionError::InstructionError(0, InstructionError::Custom(6030)) => {}
+            err => panic!("Got unexpected error: {:?}", err),
+        }
+    }
+
+    let tx_result = utils::handlers::handle_ramp_amp(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &200,
+        &(now + 2 * day),
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Ramp amp transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<AmpRampStarted> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "AmpRampStarted");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].amp_initial, amp);
+    assert_eq!(events[0].amp_target, 200);
+    assert_eq!(events[0].amp_ramp_start, now);
+    assert_eq!(events[0].amp_ramp_end, now + 2 * day);
+
+    // halfway through the ramp the pool prices at A = 150
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += day;
+    svm.set_sysvar::<Clock>(&clock);
+
+    let expected_amount_out = utils::get_utils::get_expected_stable_amount_out(
+        &amount_in, &reserve1, &reserve0, &fee_bps, &150,
+    );
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token1,
+        &amount_in,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].token_in, token1);
+    assert_eq!(events[0].amount_out, expected_amount_out);
+}
+
+#[test]
+fn test_stable_swap_pool_twap() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let (token0, token1) = utils::setup_utils::create_mints(&mut svm, &admin, &DECIMALS, &DECIMALS);
+    let amp = 100;
+    let tx_result = utils::handlers::handle_create_stable_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+        &amp,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create stable pool transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // four token1 per token0 in the vaults
+    let amount_a = 1_000_000;
+    let amount_b = 4_000_000;
+    utils::setup_utils::fund_depositor(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &amm_config,
+        &amount_a,
+        &amount_b,
+        &token0,
+        &token1,
+    );
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let first = helper_observe(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+    );
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 100;
+    svm.set_sysvar::<Clock>(&clock);
+    svm.expire_blockhash();
+    let second = helper_observe(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+    );
+
+    // the TWAP follows the slope of the curve, not the reserve ratio
+    let elapsed = (second.timestamp - first.timestamp) as u128;
+    assert_eq!(elapsed, 100);
+    let q64 = (1u128 << 64) as f64;
+    let price0 = second
+        .price0_cumulative
+        .wrapping_sub(first.price0_cumulative)
+        / elapsed;
+    let price1 = second
+        .price1_cumulative
+        .wrapping_sub(first.price1_cumulative)
+        / elapsed;
+    let expected_price0 =
+        utils::get_utils::get_expected_stable_spot_price(&amp, &amount_a, &amount_b);
+    let expected_price1 =
+        utils::get_utils::get_expected_stable_spot_price(&amp, &amount_b, &amount_a);
+    for (price, expected) in [(price0, expected_price0), (price1, expected_price1)] {
+        let price = price as f64 / q64;
+        assert!(
+            (price - expected).abs() < expected * 1e-6,
+            "price {} expected {}",
+            price,
+            expected
+        );
+    }
+    assert!(expected_price0 > 1.0 && expected_price0 < 4.0);
+}
+
+#[test]
+fn test_stable_swap_pool_validation() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let fee_bps = 5;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    // the invariant assumes both tokens are worth the same per base unit
+    let (token0, token1) = utils::setup_utils::create_mints(&mut svm, &admin, &DECIMALS, &6);
+    let tx_result = utils::handlers::handle_create_stable_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+        &100,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::DecimalsNotEqual
+        TransactionError::InstructionError(0, InstructionError::Custom(6001)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // a constant product pool has no amplification to ramp
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create pool transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    let tx_result = utils::handlers::handle_ramp_amp(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &200,
+        &(now + 2 * 86_400),
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::NotStablePool
+        TransactionError::InstructionError(0, InstructionError::Custom(6031)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+}
+
+fn helper_observe(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    payer: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> PriceObservation {
+    let tx_result =
+        utils::handlers::handle_observe(svm, program_id, amm_config, payer, token0, token1);
+    assert!(
+        tx_result.is_ok(),
+        "Observe transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    PriceObservation::deserialize(&mut &tx_result.unwrap().return_data.data[..])
+        .expect("Failed to deserialize PriceObservation")
+}
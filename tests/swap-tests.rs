@@ -9,7 +9,7 @@ use litesvm_token::{
 use sha2::{Digest, Sha256};
 use solana_program::program_pack::Pack;
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
+    instruction::{AccountMeta, Instruction, InstructionError},
     pubkey::Pubkey,
     signature::{read_keypair_file, Keypair, Signer},
     transaction::{Transaction, TransactionError},
@@ -30,9 +30,39 @@ struct AdminSettings {
 
 #[derive(Debug, BorshDeserialize)]
 struct Pool {
+    pub amm_config: Pubkey,
     pub token0: Pubkey,
     pub token1: Pubkey,
     pub liquidity: u64,
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct Swapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub token_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub amount_out_received: u64,
+    pub swap_fee: u64,
+    pub protocol_fee: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct ProtocolFeeChanged {
+    pub fee_manager: Pubkey,
+    pub protocol_fee_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct ProtocolFeesCollected {
+    pub pool: Pubkey,
+    pub treasury_withdrawer: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
 }
 
 #[test]
@@ -48,7 +78,7 @@ fn test_successful_swap() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
     svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
@@ -83,8 +113,24 @@ fn test_successful_swap() {
             .unwrap();
     }
 
-    let tx_createPool_result =
-        utils::handlers::handle_create_pool(&mut svm, &program_id, &admin, &token0, &token1);
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let tx_createPool_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
     assert!(
         tx_createPool_result.is_ok(),
         "Create transaction failed: {:?}",
@@ -92,18 +138,18 @@ fn test_successful_swap() {
     );
 
     let (pool_pda, _bump_pool_pda) =
-        utils::get_utils::get_pool_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
     let account_pool = svm.get_account(&pool_pda).expect("Should exist");
 
     let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
 
     println!("Pool exists: {}", svm.get_account(&pool_pda).is_some());
-    assert_eq!(data.liquidity, 0);
+    assert_eq!(data.liquidity, utils::get_utils::MINIMUM_LIQUIDITY);
     assert_eq!(data.token0, token0);
     assert_eq!(data.token1, token1);
 
     let (tokenliq_pda, tokenliq_bump) =
-        utils::get_utils::get_tokenliq_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
 
     let depositor_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
         .owner(&depositor.pubkey())
@@ -151,12 +197,13 @@ fn test_successful_swap() {
     let tx_deposit_result = utils::handlers::handle_deposit(
         &mut svm,
         &program_id,
-        &admin,
+        &amm_config,
         &depositor,
         &token0,
         &token1,
         &amount_a,
         &amount_b,
+        &0,
     );
 
     // maybe i should add to them init if needed but the thing is idk
@@ -182,15 +229,20 @@ fn test_successful_swap() {
     .send()
     .unwrap();
 
+    let expected_amount_out =
+        utils::get_utils::get_expected_amount_out(&alice_amount_a, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+
     let tx_swap_result = utils::handlers::handle_swapExactInput(
         &mut svm,
         &program_id,
-        &admin,
+        &amm_config,
         &alice,
         &token0,
         &token1,
         &token0,
         &alice_amount_a,
+        &expected_amount_out,
     )
     .unwrap();
 
@@ -210,4 +262,762 @@ fn test_successful_swap() {
         .amount;
 
     assert!(alice_token1_amount > alice_token0_amount);
+    assert_eq!(alice_token1_amount, expected_amount_out);
+
+    let events: Vec<Swapped> = utils::get_utils::get_events(&tx_swap_result.logs, "Swapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].swapper, alice.pubkey());
+    assert_eq!(events[0].token_in, token0);
+    assert_eq!(events[0].amount_in, alice_amount_a);
+    assert_eq!(events[0].amount_out, expected_amount_out);
+    assert_eq!(events[0].amount_out_received, expected_amount_out);
+    assert_eq!(events[0].swap_fee, alice_amount_a * fee_bps as u64 / 10_000);
+    assert_eq!(events[0].protocol_fee, 0);
+    assert_eq!(events[0].reserve0, amount_a + alice_amount_a);
+    assert_eq!(events[0].reserve1, amount_b - expected_amount_out);
+}
+
+#[test]
+fn test_swap_slippage_exceeded() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let alice_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_amount_a = 100;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token0,
+        &alice_token0_ata_account,
+        alice_amount_a,
+    )
+    .send()
+    .unwrap();
+
+    let expected_amount_out =
+        utils::get_utils::get_expected_amount_out(&alice_amount_a, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+
+    // Asking for one more token than the pool can give must revert
+    let minimum_amount_out = expected_amount_out + 1;
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &minimum_amount_out,
+    );
+    match tx_swap_result.unwrap_err().err {
+        // CustomError::SlippageExceeded
+        TransactionError::InstructionError(0, InstructionError::Custom(6006)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let alice_ata_token0 = svm
+        .get_account(&alice_token0_ata_account)
+        .expect("Should exist");
+    let alice_token0_amount = Account::unpack(&alice_ata_token0.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+    let alice_token1_amount = Account::unpack(&alice_ata_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(alice_token0_amount, alice_amount_a);
+    assert_eq!(alice_token1_amount, 0);
+}
+
+#[test]
+fn test_successful_swap_exact_output() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let alice_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    // Alice wants exactly 100 of token0 and pays with token1
+    let amount_out = 100;
+    let expected_amount_in =
+        utils::get_utils::get_expected_amount_in(&amount_out, &amount_b, &amount_a, &fee_bps)
+            .unwrap();
+
+    let alice_amount_b = 1_000;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token1,
+        &alice_token1_ata_account,
+        alice_amount_b,
+    )
+    .send()
+    .unwrap();
+
+    let tx_swap_result = utils::handlers::handle_swapExactOutput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token1,
+        &amount_out,
+        &expected_amount_in,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+
+    let alice_ata_token0 = svm
+        .get_account(&alice_token0_ata_account)
+        .expect("Should exist");
+    let alice_token0_amount = Account::unpack(&alice_ata_token0.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+    let alice_token1_amount = Account::unpack(&alice_ata_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(alice_token0_amount, amount_out);
+    assert_eq!(alice_token1_amount, alice_amount_b - expected_amount_in);
+}
+
+#[test]
+fn test_swap_exact_output_maximum_exceeded() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let amount_out = 100;
+    let expected_amount_in =
+        utils::get_utils::get_expected_amount_in(&amount_out, &amount_b, &amount_a, &fee_bps)
+            .unwrap();
+
+    let alice_amount_b = 1_000;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token1,
+        &alice_token1_ata_account,
+        alice_amount_b,
+    )
+    .send()
+    .unwrap();
+
+    // One token below the required input must revert
+    let maximum_amount_in = expected_amount_in - 1;
+    let tx_swap_result = utils::handlers::handle_swapExactOutput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token1,
+        &amount_out,
+        &maximum_amount_in,
+    );
+    match tx_swap_result.unwrap_err().err {
+        // CustomError::SlippageExceeded
+        TransactionError::InstructionError(0, InstructionError::Custom(6006)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+    let alice_token1_amount = Account::unpack(&alice_ata_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(alice_token1_amount, alice_amount_b);
+}
+
+#[test]
+fn test_successful_swap_token_2022() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    // token0 lives in Token-2022, token1 in the legacy token program
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+        &DECIMALS,
+        &DECIMALS,
+        &spl_token_2022_interface::id(),
+        &litesvm_token::spl_token::id(),
+    );
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let alice_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .token_program_id(&spl_token_2022_interface::id())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_amount_a = 100;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token0,
+        &alice_token0_ata_account,
+        alice_amount_a,
+    )
+    .token_program_id(&spl_token_2022_interface::id())
+    .send()
+    .unwrap();
+
+    let expected_amount_out =
+        utils::get_utils::get_expected_amount_out(&alice_amount_a, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &expected_amount_out,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+
+    let alice_ata_token0 = svm
+        .get_account(&alice_token0_ata_account)
+        .expect("Should exist");
+    let alice_token0_amount = utils::get_utils::get_token_balance(&alice_ata_token0.data);
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+    let alice_token1_amount = utils::get_utils::get_token_balance(&alice_ata_token1.data);
+
+    assert_eq!(alice_token0_amount, 0);
+    assert_eq!(alice_token1_amount, expected_amount_out);
+}
+
+#[test]
+fn test_swap_transfer_fee_mints() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    // both mints withhold 1% of every transfer
+    let transfer_fee_bps = 100;
+    let maximum_fee = u64::MAX;
+    let mint_a = utils::setup_utils::create_transfer_fee_mint(
+        &mut svm,
+        &admin,
+        &admin.pubkey(),
+        &DECIMALS,
+        &transfer_fee_bps,
+        &maximum_fee,
+    );
+    let mint_b = utils::setup_utils::create_transfer_fee_mint(
+        &mut svm,
+        &admin,
+        &admin.pubkey(),
+        &DECIMALS,
+        &transfer_fee_bps,
+        &maximum_fee,
+    );
+    let (token0, token1) = if mint_a < mint_b {
+        (mint_a, mint_b)
+    } else {
+        (mint_b, mint_a)
+    };
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    utils::setup_utils::setup_pool_for_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+        &token0,
+        &token1,
+    );
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let alice_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .token_program_id(&spl_token_2022_interface::id())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .token_program_id(&spl_token_2022_interface::id())
+        .send()
+        .unwrap();
+
+    let alice_amount_a = 100_000;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token0,
+        &alice_token0_ata_account,
+        alice_amount_a,
+    )
+    .token_program_id(&spl_token_2022_interface::id())
+    .send()
+    .unwrap();
+
+    // the deposit itself paid the transfer fee on the way into the vaults
+    let reserve0 = amount_a
+        - utils::get_utils::get_expected_transfer_fee(&amount_a, &transfer_fee_bps, &maximum_fee);
+    let reserve1 = amount_b
+        - utils::get_utils::get_expected_transfer_fee(&amount_b, &transfer_fee_bps, &maximum_fee);
+    let received_in = alice_amount_a
+        - utils::get_utils::get_expected_transfer_fee(
+            &alice_amount_a,
+            &transfer_fee_bps,
+            &maximum_fee,
+        );
+    let amount_out =
+        utils::get_utils::get_expected_amount_out(&received_in, &reserve0, &reserve1, &fee_bps)
+            .unwrap();
+    let received_out = amount_out
+        - utils::get_utils::get_expected_transfer_fee(&amount_out, &transfer_fee_bps, &maximum_fee);
+
+    // asking for the gross output must fail, the minimum applies to what alice receives
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &amount_out,
+    );
+    assert!(tx_swap_result.is_err(), "Swap should have failed");
+
+    svm.expire_blockhash();
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &alice_amount_a,
+        &received_out,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+
+    assert_eq!(
+        utils::get_utils::get_token_balance(&alice_ata_token1.data),
+        received_out
+    );
+}
+
+#[test]
+fn test_protocol_fee_collected() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool_with_liquidity(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    // a fifth of every swap fee goes to the protocol
+    let protocol_fee_bps = 2_000;
+    let tx_result =
+        utils::handlers::handle_set_protocol_fee(&mut svm, &program_id, &alice, &protocol_fee_bps);
+    assert!(tx_result.is_err());
+
+    let tx_result =
+        utils::handlers::handle_set_protocol_fee(&mut svm, &program_id, &admin, &protocol_fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Set protocol fee transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<ProtocolFeeChanged> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "ProtocolFeeChanged");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].fee_manager, admin.pubkey());
+    assert_eq!(events[0].protocol_fee_bps, protocol_fee_bps);
+
+    let alice_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+
+    let alice_amount_a = 2_000_000;
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token0,
+        &alice_token0_ata_account,
+        alice_amount_a,
+    )
+    .send()
+    .unwrap();
+
+    let swap_amount = 1_000_000;
+    let first_amount_out =
+        utils::get_utils::get_expected_amount_out(&swap_amount, &amount_a, &amount_b, &fee_bps)
+            .unwrap();
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &swap_amount,
+        &first_amount_out,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+
+    // the protocol's share sits in the vault but is no longer priced in
+    let protocol_fee = swap_amount * fee_bps as u64 / 10_000 * protocol_fee_bps as u64 / 10_000;
+    let reserve0 = amount_a + swap_amount - protocol_fee;
+    let reserve1 = amount_b - first_amount_out;
+    let second_amount_out =
+        utils::get_utils::get_expected_amount_out(&swap_amount, &reserve0, &reserve1, &fee_bps)
+            .unwrap();
+
+    svm.expire_blockhash();
+    let tx_swap_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &swap_amount,
+        &second_amount_out,
+    );
+    assert!(
+        tx_swap_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_swap_result.err()
+    );
+
+    let alice_ata_token1 = svm
+        .get_account(&alice_token1_ata_account)
+        .expect("Should exist");
+    let alice_token1_amount = Account::unpack(&alice_ata_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+    assert_eq!(alice_token1_amount, first_amount_out + second_amount_out);
+
+    let treasury_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
+        .owner(&admin.pubkey())
+        .send()
+        .unwrap();
+
+    let treasury_token1_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token1)
+        .owner(&admin.pubkey())
+        .send()
+        .unwrap();
+
+    // the fees can only go to accounts of the treasury
+    let tx_result = utils::handlers::handle_collect_protocol_fees(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &alice_token0_ata_account,
+        &alice_token1_ata_account,
+    );
+    match tx_result.unwrap_err().err {
+        // anchor ConstraintTokenOwner
+        TransactionError::InstructionError(0, InstructionError::Custom(2015)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_collect_protocol_fees(
+        &mut svm,
+        &program_id,
+        &admin,
+        &amm_config,
+        &token0,
+        &token1,
+        &treasury_token0_ata_account,
+        &treasury_token1_ata_account,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Collect transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let (pool_pda, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let events: Vec<ProtocolFeesCollected> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "ProtocolFeesCollected");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].treasury_withdrawer, admin.pubkey());
+    assert_eq!(events[0].amount0, 2 * protocol_fee);
+    assert_eq!(events[0].amount1, 0);
+
+    let treasury_token0 = svm
+        .get_account(&treasury_token0_ata_account)
+        .expect("Should exist");
+    let treasury_token0_amount = Account::unpack(&treasury_token0.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    let treasury_token1 = svm
+        .get_account(&treasury_token1_ata_account)
+        .expect("Should exist");
+    let treasury_token1_amount = Account::unpack(&treasury_token1.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(treasury_token0_amount, 2 * protocol_fee);
+    assert_eq!(treasury_token1_amount, 0);
 }
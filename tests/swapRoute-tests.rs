@@ -0,0 +1,499 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::{
+        spl_token::native_mint::DECIMALS, CreateAssociatedTokenAccount, CreateMint, MintTo,
+    },
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+    spl_associated_token_account::{
+        get_associated_token_address, get_associated_token_address_with_program_id,
+    },
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct Swapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub token_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub amount_out_received: u64,
+    pub swap_fee: u64,
+    pub protocol_fee: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[test]
+fn test_swap_route_two_hops() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let mut mints: Vec<Pubkey> = (0..3)
+        .map(|_| {
+            CreateMint::new(&mut svm, &admin)
+                .authority(&admin.pubkey())
+                .decimals(DECIMALS)
+                .send()
+                .unwrap()
+        })
+        .collect();
+    mints.sort();
+    let (token_a, token_b, token_c) = (mints[0], mints[1], mints[2]);
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    // A/B pool
+    let reserve_a = 1_000_000;
+    let reserve_ab = 2_000_000;
+    utils::setup_utils::setup_pool_for_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &reserve_a,
+        &reserve_ab,
+        &token_a,
+        &token_b,
+    );
+    helper_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token_a,
+        &token_b,
+        &reserve_a,
+        &reserve_ab,
+    );
+
+    // B/C pool in the same fee tier
+    let reserve_bc = 3_000_000;
+    let reserve_c = 5_000_000;
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token_b,
+        &token_c,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (tokenliq_bc, _bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token_b, &token_c);
+    CreateAssociatedTokenAccount::new(&mut svm, &admin, &tokenliq_bc)
+        .owner(&depositor.pubkey())
+        .send()
+        .unwrap();
+    let depositor_token_c_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token_c)
+        .owner(&depositor.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token_b,
+        &get_associated_token_address(&depositor.pubkey(), &token_b),
+        reserve_bc,
+    )
+    .send()
+    .unwrap();
+    MintTo::new(
+        &mut svm,
+        &admin,
+        &token_c,
+        &depositor_token_c_ata,
+        reserve_c,
+    )
+    .send()
+    .unwrap();
+    helper_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token_b,
+        &token_c,
+        &reserve_bc,
+        &reserve_c,
+    );
+
+    // alice only holds A and C, the B leg never touches her
+    let amount_in = 10_000;
+    let alice_token_a_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token_a)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+    let alice_token_c_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token_c)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &admin, &token_a, &alice_token_a_ata, amount_in)
+        .send()
+        .unwrap();
+
+    let hops = [
+        (amm_config, token_a, token_b),
+        (amm_config, token_b, token_c),
+    ];
+    let amount_b =
+        utils::get_utils::get_expected_amount_out(&amount_in, &reserve_a, &reserve_ab, &fee_bps)
+            .unwrap();
+    let amount_out =
+        utils::get_utils::get_expected_amount_out(&amount_b, &reserve_bc, &reserve_c, &fee_bps)
+            .unwrap();
+
+    // the minimum is only checked against the final output
+    let tx_result = utils::handlers::handle_swap_route(
+        &mut svm,
+        &program_id,
+        &alice,
+        &alice_token_a_ata,
+        &alice_token_c_ata,
+        &hops,
+        &amount_in,
+        &(amount_out + 1),
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::SlippageExceeded
+        TransactionError::InstructionError(0, InstructionError::Custom(6006)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // the destination has to hold the mint the route ends in
+    let tx_result = utils::handlers::handle_swap_route(
+        &mut svm,
+        &program_id,
+        &alice,
+        &alice_token_a_ata,
+        &alice_token_a_ata,
+        &hops,
+        &amount_in,
+        &amount_out,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidRoute
+        TransactionError::InstructionError(0, InstructionError::Custom(6023)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    // the same pool can't be visited twice
+    let tx_result = utils::handlers::handle_swap_route(
+        &mut svm,
+        &program_id,
+        &alice,
+        &alice_token_a_ata,
+        &alice_token_a_ata,
+        &[hops[0], hops[0]],
+        &amount_in,
+        &0,
+    );
+    match tx_result.unwrap_err().err {
+        // CustomError::InvalidRoute
+        TransactionError::InstructionError(0, InstructionError::Custom(6023)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_result = utils::handlers::handle_swap_route(
+        &mut svm,
+        &program_id,
+        &alice,
+        &alice_token_a_ata,
+        &alice_token_c_ata,
+        &hops,
+        &amount_in,
+        &amount_out,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap route transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let swapped: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(swapped.len(), 2);
+    assert_eq!(swapped[0].token_in, token_a);
+    assert_eq!(swapped[0].amount_in, amount_in);
+    assert_eq!(swapped[0].amount_out, amount_b);
+    assert_eq!(swapped[1].token_in, token_b);
+    assert_eq!(swapped[1].amount_in, amount_b);
+    assert_eq!(swapped[1].amount_out, amount_out);
+    assert_eq!(swapped[1].swapper, alice.pubkey());
+
+    let alice_token_a_balance =
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token_a_ata).unwrap().data);
+    let alice_token_c_balance =
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token_c_ata).unwrap().data);
+    assert_eq!(alice_token_a_balance, 0);
+    assert_eq!(alice_token_c_balance, amount_out);
+    assert!(svm
+        .get_account(&get_associated_token_address(&alice.pubkey(), &token_b))
+        .is_none());
+
+    // pool B/C received the intermediate amount straight from pool A/B
+    let (pool_bc, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token_b, &token_c);
+    let (pool_bc_authority, _bump) =
+        utils::get_utils::get_pool_authority_pda(&program_id, &pool_bc, &token_b, &token_c);
+    let vault_b = get_associated_token_address(&pool_bc_authority, &token_b);
+    let vault_b_balance =
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_b).unwrap().data);
+    assert_eq!(vault_b_balance, reserve_bc + amount_b);
+}
+
+#[test]
+fn test_swap_route_transfer_fee_middle_mint() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let depositor_bc = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&depositor_bc.pubkey(), 1_000_000_000_000)
+        .unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    // A and C are plain SPL mints, B withholds 1% of every transfer
+    let transfer_fee_bps = 100;
+    let maximum_fee = u64::MAX;
+    let token_a = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let token_b = utils::setup_utils::create_transfer_fee_mint(
+        &mut svm,
+        &admin,
+        &admin.pubkey(),
+        &DECIMALS,
+        &transfer_fee_bps,
+        &maximum_fee,
+    );
+    let token_c = CreateMint::new(&mut svm, &admin)
+        .authority(&admin.pubkey())
+        .decimals(DECIMALS)
+        .send()
+        .unwrap();
+    let pool_ab = helper_sort(token_a, token_b);
+    let pool_bc = helper_sort(token_b, token_c);
+
+    let fee_bps = 30;
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let amount = 5_000_000;
+    utils::setup_utils::setup_pool_for_mints(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount,
+        &amount,
+        &pool_ab.0,
+        &pool_ab.1,
+    );
+    helper_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &pool_ab.0,
+        &pool_ab.1,
+        &amount,
+        &amount,
+    );
+
+    let tx_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &pool_bc.0,
+        &pool_bc.1,
+        &fee_bps,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+    utils::setup_utils::fund_depositor(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor_bc,
+        &amm_config,
+        &amount,
+        &amount,
+        &pool_bc.0,
+        &pool_bc.1,
+    );
+    helper_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor_bc,
+        &pool_bc.0,
+        &pool_bc.1,
+        &amount,
+        &amount,
+    );
+
+    // the B deposits lost their transfer fee on the way in, so price on the vaults
+    let vault_ab_a = helper_vault(&svm, &program_id, &amm_config, pool_ab, &token_a);
+    let vault_ab_b = helper_vault(&svm, &program_id, &amm_config, pool_ab, &token_b);
+    let vault_bc_b = helper_vault(&svm, &program_id, &amm_config, pool_bc, &token_b);
+    let vault_bc_c = helper_vault(&svm, &program_id, &amm_config, pool_bc, &token_c);
+    let reserve_ab_a =
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_ab_a).unwrap().data);
+    let reserve_ab_b =
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_ab_b).unwrap().data);
+    let reserve_bc_b =
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_bc_b).unwrap().data);
+    let reserve_bc_c =
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_bc_c).unwrap().data);
+
+    let amount_in = 10_000;
+    let alice_token_a_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token_a)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+    let alice_token_c_ata = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token_c)
+        .owner(&alice.pubkey())
+        .send()
+        .unwrap();
+    MintTo::new(&mut svm, &admin, &token_a, &alice_token_a_ata, amount_in)
+        .send()
+        .unwrap();
+
+    // B is charged its transfer fee once, between the two pools
+    let amount_b = utils::get_utils::get_expected_amount_out(
+        &amount_in,
+        &reserve_ab_a,
+        &reserve_ab_b,
+        &fee_bps,
+    )
+    .unwrap();
+    let amount_b_received = amount_b
+        - utils::get_utils::get_expected_transfer_fee(&amount_b, &transfer_fee_bps, &maximum_fee);
+    let amount_out = utils::get_utils::get_expected_amount_out(
+        &amount_b_received,
+        &reserve_bc_b,
+        &reserve_bc_c,
+        &fee_bps,
+    )
+    .unwrap();
+
+    let tx_result = utils::handlers::handle_swap_route(
+        &mut svm,
+        &program_id,
+        &alice,
+        &alice_token_a_ata,
+        &alice_token_c_ata,
+        &[
+            (amm_config, pool_ab.0, pool_ab.1),
+            (amm_config, pool_bc.0, pool_bc.1),
+        ],
+        &amount_in,
+        &amount_out,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap route transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let swapped: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(swapped.len(), 2);
+    assert_eq!(swapped[0].amount_out, amount_b);
+    assert_eq!(swapped[0].amount_out_received, amount_b_received);
+    assert_eq!(swapped[1].token_in, token_b);
+    assert_eq!(swapped[1].amount_in, amount_b_received);
+    assert_eq!(swapped[1].amount_out, amount_out);
+
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&alice_token_c_ata).unwrap().data),
+        amount_out
+    );
+    assert_eq!(
+        utils::get_utils::get_token_balance(&svm.get_account(&vault_bc_b).unwrap().data),
+        reserve_bc_b + amount_b_received
+    );
+}
+
+fn helper_sort(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Vault of `mint` in the pool of `tokens`, under the mint's own token program
+fn helper_vault(
+    svm: &LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    tokens: (Pubkey, Pubkey),
+    mint: &Pubkey,
+) -> Pubkey {
+    let (pool, _bump) =
+        utils::get_utils::get_pool_pda(program_id, amm_config, &tokens.0, &tokens.1);
+    let (pool_authority, _bump) =
+        utils::get_utils::get_pool_authority_pda(program_id, &pool, &tokens.0, &tokens.1);
+    let token_program = utils::handlers::get_token_program(svm, mint);
+
+    get_associated_token_address_with_program_id(&pool_authority, mint, &token_program)
+}
+
+fn helper_deposit(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    depositor: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    amount0: &u64,
+    amount1: &u64,
+) {
+    let tx_result = utils::handlers::handle_deposit(
+        svm, program_id, amm_config, depositor, token0, token1, amount0, amount1, &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+}
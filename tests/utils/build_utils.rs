@@ -6,7 +6,6 @@ use {
         transaction::{Transaction, TransactionError},
     },
     solana_system_interface::program::ID as system_program_id,
-    spl_associated_token_account::get_associated_token_address,
     spl_associated_token_account::get_associated_token_address_with_program_id,
 };
 
@@ -17,26 +16,54 @@ pub fn build_createPool_instruction(
     admin: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    token0_allowed: &bool,
+    token1_allowed: &bool,
+    has_creator_role: &bool,
+    treasury: &Pubkey,
+    fee_bps: &u16,
 ) -> Instruction {
     let discriminator = get_utils::get_discriminator("create_pool");
     let mut instruction_data = Vec::new();
     instruction_data.extend_from_slice(&discriminator);
 
     let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (amm_config, _bump_config) = get_utils::get_amm_config_pda(program_id, fee_bps);
     let (token0_vault_pda, _bump_token0_vault) =
         get_utils::get_token_vault_pda(program_id, admin, token0);
     let (token1_vault_pda, _bump_token1_vault) =
         get_utils::get_token_vault_pda(program_id, admin, token1);
     let (tokenliq_pda, _bump_tokenliq) =
-        get_utils::get_tokenliq_pda(program_id, admin, token0, token1);
-    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, admin, token0, token1);
+        get_utils::get_tokenliq_pda(program_id, &amm_config, token0, token1);
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, &amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
     let (pool_authority_pda, _bump_pool_authority) =
         get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
 
     let token0_vault =
-        get_associated_token_address_with_program_id(&pool_authority_pda, token0, &spl_token::id());
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
     let token1_vault =
-        get_associated_token_address_with_program_id(&pool_authority_pda, token1, &spl_token::id());
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
+    let locked_liquidity = get_associated_token_address_with_program_id(
+        &pool_authority_pda,
+        &tokenliq_pda,
+        &spl_token::id(),
+    );
+    // the program id stands in for an absent optional account
+    let creator_role = match has_creator_role {
+        true => get_utils::get_role_pda(program_id, admin).0,
+        false => *program_id,
+    };
+    let token0_allowlist = match token0_allowed {
+        true => get_utils::get_allowed_mint_pda(program_id, token0).0,
+        false => *program_id,
+    };
+    let token1_allowlist = match token1_allowed {
+        true => get_utils::get_allowed_mint_pda(program_id, token1).0,
+        false => *program_id,
+    };
 
     Instruction {
         program_id: *program_id,
@@ -44,15 +71,97 @@ pub fn build_createPool_instruction(
             AccountMeta::new(*admin, true),
             AccountMeta::new_readonly(*token0, false),
             AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new_readonly(amm_config, false),
             AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
             AccountMeta::new(pool_authority_pda, false),
             AccountMeta::new(token0_vault, false),
             AccountMeta::new(token1_vault, false),
             AccountMeta::new(admin_settings, false),
+            AccountMeta::new_readonly(creator_role, false),
             AccountMeta::new(tokenliq_pda, false),
+            AccountMeta::new(locked_liquidity, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program_id, false),
             AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(token0_allowlist, false),
+            AccountMeta::new_readonly(token1_allowlist, false),
+            AccountMeta::new(*treasury, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_createAmmConfig_instruction(
+    program_id: &Pubkey,
+    fee_manager: &Pubkey,
+    fee_bps: &u16,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("create_amm_config");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&fee_bps.to_le_bytes());
+
+    let (fee_manager_role, _bump) = get_utils::get_role_pda(program_id, fee_manager);
+    let (amm_config, _bump_config) = get_utils::get_amm_config_pda(program_id, fee_bps);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*fee_manager, true),
+            AccountMeta::new_readonly(fee_manager_role, false),
+            AccountMeta::new(amm_config, false),
+            AccountMeta::new_readonly(system_program_id, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_allowMint_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("allow_mint");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (allowed_mint, _bump_allowed_mint) = get_utils::get_allowed_mint_pda(program_id, mint);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(allowed_mint, false),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new_readonly(system_program_id, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_disallowMint_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("disallow_mint");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (allowed_mint, _bump_allowed_mint) = get_utils::get_allowed_mint_pda(program_id, mint);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(allowed_mint, false),
+            AccountMeta::new_readonly(admin_settings, false),
         ],
         data: instruction_data,
     }
@@ -60,12 +169,15 @@ pub fn build_createPool_instruction(
 
 pub fn build_deposit_instruction(
     program_id: &Pubkey,
-    admin_pub: &Pubkey,
+    amm_config: &Pubkey,
     depositor: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
     amount_a: &u64,
     amount_b: &u64,
+    min_liquidity_out: &u64,
 ) -> Instruction {
     let discriminator = get_utils::get_discriminator("deposit");
     let mut instruction_data = Vec::new();
@@ -77,19 +189,30 @@ pub fn build_deposit_instruction(
     let amount_b_bytes = amount_b.to_le_bytes();
     instruction_data.extend_from_slice(&amount_b_bytes);
 
+    let min_liquidity_out_bytes = min_liquidity_out.to_le_bytes();
+    instruction_data.extend_from_slice(&min_liquidity_out_bytes);
+
     let (tokenliq_pda, tokenliq_bump) =
-        get_utils::get_tokenliq_pda(program_id, admin_pub, token0, token1);
+        get_utils::get_tokenliq_pda(program_id, amm_config, token0, token1);
     //token0 vault
-    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, admin_pub, token0, token1);
+    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
     let (pool_authority_pda, pool_bump) =
         get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
-    let token0_vault = get_associated_token_address(&pool_authority_pda, token0);
-    let token1_vault = get_associated_token_address(&pool_authority_pda, token1);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
 
-    let token0_depositor_ata = get_associated_token_address(depositor, token0);
-    let token1_depositor_ata = get_associated_token_address(depositor, token1);
+    let token0_depositor_ata =
+        get_associated_token_address_with_program_id(depositor, token0, token0_program);
+    let token1_depositor_ata =
+        get_associated_token_address_with_program_id(depositor, token1, token1_program);
 
-    let tokenliq_depositor_ata = get_associated_token_address(depositor, &tokenliq_pda);
+    let tokenliq_depositor_ata =
+        get_associated_token_address_with_program_id(depositor, &tokenliq_pda, &spl_token::id());
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
 
     Instruction {
         program_id: *program_id,
@@ -104,8 +227,12 @@ pub fn build_deposit_instruction(
             AccountMeta::new(token1_depositor_ata, false),
             AccountMeta::new(tokenliq_depositor_ata, false),
             AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
+            AccountMeta::new_readonly(admin_settings, false),
             AccountMeta::new(pool_authority_pda, false),
             // idk if its safe
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
             AccountMeta::new_readonly(spl_token::id(), false),
             AccountMeta::new_readonly(system_program_id, false),
             AccountMeta::new_readonly(spl_associated_token_account::id(), false),
@@ -116,11 +243,15 @@ pub fn build_deposit_instruction(
 
 pub fn build_withdraw_instruction(
     program_id: &Pubkey,
-    admin_pub: &Pubkey,
+    amm_config: &Pubkey,
     depositor_pub: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
     amount: &u64,
+    min_amount0_out: &u64,
+    min_amount1_out: &u64,
 ) -> Instruction {
     let discriminator = get_utils::get_discriminator("withdraw");
     let mut instruction_data = Vec::new();
@@ -129,19 +260,35 @@ pub fn build_withdraw_instruction(
     let amount_bytes = amount.to_le_bytes();
     instruction_data.extend_from_slice(&amount_bytes);
 
+    let min_amount0_out_bytes = min_amount0_out.to_le_bytes();
+    instruction_data.extend_from_slice(&min_amount0_out_bytes);
+
+    let min_amount1_out_bytes = min_amount1_out.to_le_bytes();
+    instruction_data.extend_from_slice(&min_amount1_out_bytes);
+
     let (tokenliq_pda, tokenliq_bump) =
-        get_utils::get_tokenliq_pda(program_id, admin_pub, token0, token1);
+        get_utils::get_tokenliq_pda(program_id, amm_config, token0, token1);
     //token0 vault
-    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, admin_pub, token0, token1);
+    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
     let (pool_authority_pda, pool_bump) =
         get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
-    let token0_vault = get_associated_token_address(&pool_authority_pda, token0);
-    let token1_vault = get_associated_token_address(&pool_authority_pda, token1);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
 
-    let token0_depositor_ata = get_associated_token_address(depositor_pub, token0);
-    let token1_depositor_ata = get_associated_token_address(depositor_pub, token1);
+    let token0_depositor_ata =
+        get_associated_token_address_with_program_id(depositor_pub, token0, token0_program);
+    let token1_depositor_ata =
+        get_associated_token_address_with_program_id(depositor_pub, token1, token1_program);
 
-    let tokenliq_depositor_ata = get_associated_token_address(depositor_pub, &tokenliq_pda);
+    let tokenliq_depositor_ata = get_associated_token_address_with_program_id(
+        depositor_pub,
+        &tokenliq_pda,
+        &spl_token::id(),
+    );
 
     Instruction {
         program_id: *program_id,
@@ -150,6 +297,7 @@ pub fn build_withdraw_instruction(
             AccountMeta::new_readonly(*token0, false),
             AccountMeta::new_readonly(*token1, false),
             AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
             AccountMeta::new(pool_authority_pda, false),
             AccountMeta::new(tokenliq_pda, false),
             AccountMeta::new(token0_vault, false),
@@ -157,6 +305,8 @@ pub fn build_withdraw_instruction(
             AccountMeta::new(token0_depositor_ata, false),
             AccountMeta::new(token1_depositor_ata, false),
             AccountMeta::new(tokenliq_depositor_ata, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
             AccountMeta::new_readonly(spl_token::id(), false),
         ],
         data: instruction_data,
@@ -172,24 +322,29 @@ pub fn build_initAdmin_instruction(program_id: &Pubkey, admin: &Pubkey) -> Instr
     instruction_data.extend_from_slice(&admin_pubkey);
 
     let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (program_data, _bump_program_data) = get_utils::get_program_data_address(program_id);
+    let (admin_role, _bump_admin_role) = get_utils::get_role_pda(program_id, admin);
 
     Instruction {
         program_id: *program_id,
         accounts: vec![
             AccountMeta::new(*admin, true),
             AccountMeta::new(admin_settings, false),
+            AccountMeta::new(admin_role, false),
+            AccountMeta::new_readonly(*program_id, false),
+            AccountMeta::new_readonly(program_data, false),
             AccountMeta::new_readonly(system_program_id, false),
         ],
         data: instruction_data,
     }
 }
 
-pub fn build_setAdmin_instruction(
+pub fn build_proposeAdmin_instruction(
     program_id: &Pubkey,
     admin: &Pubkey,
     new_admin: &Pubkey,
 ) -> Instruction {
-    let discriminator = get_utils::get_discriminator("set_admin");
+    let discriminator = get_utils::get_discriminator("propose_admin");
     let mut instruction_data = Vec::new();
     instruction_data.extend_from_slice(&discriminator);
 
@@ -208,14 +363,289 @@ pub fn build_setAdmin_instruction(
     }
 }
 
+// `admin` is the current admin, whose roles move to `pending_admin`
+pub fn build_acceptAdmin_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    pending_admin: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("accept_admin");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (previous_admin_role, _bump_previous) = get_utils::get_role_pda(program_id, admin);
+    let (admin_role, _bump_role) = get_utils::get_role_pda(program_id, pending_admin);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*pending_admin, true),
+            AccountMeta::new(admin_settings, false),
+            AccountMeta::new(previous_admin_role, false),
+            AccountMeta::new(admin_role, false),
+            AccountMeta::new_readonly(system_program_id, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_cancelAdminProposal_instruction(program_id: &Pubkey, admin: &Pubkey) -> Instruction {
+    let discriminator = get_utils::get_discriminator("cancel_admin_proposal");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(admin_settings, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_grantRole_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    holder: &Pubkey,
+    roles: &u8,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("grant_role");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&holder.to_bytes());
+    instruction_data.push(*roles);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (role_assignment, _bump_role) = get_utils::get_role_pda(program_id, holder);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new(role_assignment, false),
+            AccountMeta::new_readonly(system_program_id, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_revokeRole_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    holder: &Pubkey,
+    roles: &u8,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("revoke_role");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.push(*roles);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (role_assignment, _bump_role) = get_utils::get_role_pda(program_id, holder);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new(role_assignment, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_setProtocolFee_instruction(
+    program_id: &Pubkey,
+    fee_manager: &Pubkey,
+    protocol_fee_bps: &u16,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("set_protocol_fee");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let protocol_fee_bps_bytes = protocol_fee_bps.to_le_bytes();
+    instruction_data.extend_from_slice(&protocol_fee_bps_bytes);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (fee_manager_role, _bump_role) = get_utils::get_role_pda(program_id, fee_manager);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*fee_manager, true),
+            AccountMeta::new_readonly(fee_manager_role, false),
+            AccountMeta::new(admin_settings, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_setGlobalPause_instruction(
+    program_id: &Pubkey,
+    pauser: &Pubkey,
+    paused: &bool,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("set_global_pause");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.push(*paused as u8);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (pauser_role, _bump_role) = get_utils::get_role_pda(program_id, pauser);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*pauser, true),
+            AccountMeta::new_readonly(pauser_role, false),
+            AccountMeta::new(admin_settings, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+// `status` is the PoolStatus variant index: 0 = Active, 1 = Paused
+pub fn build_setPoolStatus_instruction(
+    program_id: &Pubkey,
+    pauser: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    status: &u8,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("set_pool_status");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.push(*status);
+
+    let (pauser_role, _bump_role) = get_utils::get_role_pda(program_id, pauser);
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*pauser, true),
+            AccountMeta::new_readonly(pauser_role, false),
+            AccountMeta::new(pool_pda, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_setPoolCreationConfig_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    permissionless: &bool,
+    creation_fee: &u64,
+    treasury: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("set_pool_creation_config");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.push(*permissionless as u8);
+    instruction_data.extend_from_slice(&creation_fee.to_le_bytes());
+    instruction_data.extend_from_slice(&treasury.to_bytes());
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(admin_settings, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_delistPool_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("delist_pool");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new(pool_pda, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_collectProtocolFees_instruction(
+    program_id: &Pubkey,
+    treasury_withdrawer: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    treasury_account_0: &Pubkey,
+    treasury_account_1: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("collect_protocol_fees");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (treasury_withdrawer_role, _bump_role) =
+        get_utils::get_role_pda(program_id, treasury_withdrawer);
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (pool_authority_pda, _bump_pool_authority) =
+        get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*treasury_withdrawer, true),
+            AccountMeta::new_readonly(treasury_withdrawer_role, false),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new_readonly(*token0, false),
+            AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new_readonly(pool_authority_pda, false),
+            AccountMeta::new(token0_vault, false),
+            AccountMeta::new(token1_vault, false),
+            AccountMeta::new(*treasury_account_0, false),
+            AccountMeta::new(*treasury_account_1, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
+        ],
+        data: instruction_data,
+    }
+}
+
 pub fn build_swapExactInput_instruction(
     program_id: &Pubkey,
-    admin_pub: &Pubkey,
+    amm_config: &Pubkey,
     swapper: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
     token_in: &Pubkey,
     amount: &u64,
+    minimum_amount_out: &u64,
 ) -> Instruction {
     let discriminator = get_utils::get_discriminator("swapExactInput");
     let mut instruction_data = Vec::new();
@@ -224,14 +654,24 @@ pub fn build_swapExactInput_instruction(
     let amount_bytes = amount.to_le_bytes();
     instruction_data.extend_from_slice(&amount_bytes);
 
-    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, admin_pub, token0, token1);
+    let minimum_amount_out_bytes = minimum_amount_out.to_le_bytes();
+    instruction_data.extend_from_slice(&minimum_amount_out_bytes);
+
+    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
     let (pool_authority_pda, pool_bump) =
         get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
-    let token0_vault = get_associated_token_address(&pool_authority_pda, token0);
-    let token1_vault = get_associated_token_address(&pool_authority_pda, token1);
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
 
-    let token0_swapper_ata = get_associated_token_address(swapper, token0);
-    let token1_swapper_ata = get_associated_token_address(swapper, token1);
+    let token0_swapper_ata =
+        get_associated_token_address_with_program_id(swapper, token0, token0_program);
+    let token1_swapper_ata =
+        get_associated_token_address_with_program_id(swapper, token1, token1_program);
 
     Instruction {
         program_id: *program_id,
@@ -241,13 +681,451 @@ pub fn build_swapExactInput_instruction(
             AccountMeta::new_readonly(*token1, false),
             AccountMeta::new_readonly(*token_in, false),
             AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
+            AccountMeta::new_readonly(admin_settings, false),
             AccountMeta::new(pool_authority_pda, false),
             AccountMeta::new(token0_vault, false),
             AccountMeta::new(token1_vault, false),
             AccountMeta::new(token0_swapper_ata, false),
             AccountMeta::new(token1_swapper_ata, false),
-            AccountMeta::new(spl_token::id(), false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_swapExactOutput_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    swapper: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    token_in: &Pubkey,
+    amount_out: &u64,
+    maximum_amount_in: &u64,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("swap_exact_output");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let amount_out_bytes = amount_out.to_le_bytes();
+    instruction_data.extend_from_slice(&amount_out_bytes);
+
+    let maximum_amount_in_bytes = maximum_amount_in.to_le_bytes();
+    instruction_data.extend_from_slice(&maximum_amount_in_bytes);
+
+    let (pool_pda, pool_bump) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
+    let (pool_authority_pda, pool_bump) =
+        get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
+
+    let token0_swapper_ata =
+        get_associated_token_address_with_program_id(swapper, token0, token0_program);
+    let token1_swapper_ata =
+        get_associated_token_address_with_program_id(swapper, token1, token1_program);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*swapper, true),
+            AccountMeta::new_readonly(*token0, false),
+            AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new_readonly(*token_in, false),
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new(pool_authority_pda, false),
+            AccountMeta::new(token0_vault, false),
+            AccountMeta::new(token1_vault, false),
+            AccountMeta::new(token0_swapper_ata, false),
+            AccountMeta::new(token1_swapper_ata, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_observe_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("observe");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*token0, false),
+            AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new_readonly(observations_pda, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+pub fn build_getTwap_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    start_timestamp: &i64,
+    end_timestamp: &i64,
+) -> Instruction {
+    let mut ix = build_observe_instruction(program_id, amm_config, token0, token1);
+
+    let discriminator = get_utils::get_discriminator("get_twap");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&start_timestamp.to_le_bytes());
+    instruction_data.extend_from_slice(&end_timestamp.to_le_bytes());
+    ix.data = instruction_data;
+    ix
+}
+
+pub fn build_growObservations_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    new_capacity: &u16,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("grow_observations");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&new_capacity.to_le_bytes());
+
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
+            AccountMeta::new_readonly(system_program_id, false),
         ],
         data: instruction_data,
     }
 }
+
+// `hops` holds (amm_config, token0, token1, token0_program, token1_program) of
+// each pool on the route, starting with the pool holding the source mint
+pub fn build_swapRoute_instruction(
+    program_id: &Pubkey,
+    swapper: &Pubkey,
+    user_source: &Pubkey,
+    user_destination: &Pubkey,
+    hops: &[(Pubkey, Pubkey, Pubkey, Pubkey, Pubkey)],
+    amount_in: &u64,
+    minimum_amount_out: &u64,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("swap_route");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&amount_in.to_le_bytes());
+    instruction_data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+
+    let mut accounts = vec![
+        AccountMeta::new(*swapper, true),
+        AccountMeta::new_readonly(admin_settings, false),
+        AccountMeta::new(*user_source, false),
+        AccountMeta::new(*user_destination, false),
+    ];
+
+    for (amm_config, token0, token1, token0_program, token1_program) in hops {
+        let (pool_pda, _bump_pool) =
+            get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+        let (observations_pda, _bump_observations) =
+            get_utils::get_observations_pda(program_id, &pool_pda);
+        let (pool_authority_pda, _bump_authority) =
+            get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
+        let token0_vault = get_associated_token_address_with_program_id(
+            &pool_authority_pda,
+            token0,
+            token0_program,
+        );
+        let token1_vault = get_associated_token_address_with_program_id(
+            &pool_authority_pda,
+            token1,
+            token1_program,
+        );
+
+        accounts.extend([
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new(observations_pda, false),
+            AccountMeta::new_readonly(pool_authority_pda, false),
+            AccountMeta::new_readonly(*token0, false),
+            AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new(token0_vault, false),
+            AccountMeta::new(token1_vault, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
+        ]);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction_data,
+    }
+}
+
+pub fn build_flashBorrow_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    borrower: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    amount0: &u64,
+    amount1: &u64,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("flash_borrow");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&amount0.to_le_bytes());
+    instruction_data.extend_from_slice(&amount1.to_le_bytes());
+
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (pool_authority_pda, _bump_pool_authority) =
+        get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
+    let borrower_token0_ata =
+        get_associated_token_address_with_program_id(borrower, token0, token0_program);
+    let borrower_token1_ata =
+        get_associated_token_address_with_program_id(borrower, token1, token1_program);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*borrower, true),
+            AccountMeta::new_readonly(*token0, false),
+            AccountMeta::new_readonly(*token1, false),
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new_readonly(admin_settings, false),
+            AccountMeta::new_readonly(pool_authority_pda, false),
+            AccountMeta::new(token0_vault, false),
+            AccountMeta::new(token1_vault, false),
+            AccountMeta::new(borrower_token0_ata, false),
+            AccountMeta::new(borrower_token1_ata, false),
+            AccountMeta::new_readonly(get_utils::INSTRUCTIONS_SYSVAR_ID, false),
+            AccountMeta::new_readonly(*token0_program, false),
+            AccountMeta::new_readonly(*token1_program, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+// Same accounts as flash_borrow
+pub fn build_flashRepay_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    borrower: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+) -> Instruction {
+    let mut ix = build_flashBorrow_instruction(
+        program_id,
+        amm_config,
+        borrower,
+        token0,
+        token1,
+        token0_program,
+        token1_program,
+        &0,
+        &0,
+    );
+    ix.data = get_utils::get_discriminator("flash_repay").to_vec();
+    ix
+}
+
+// Amounts out go to the swapper's ATAs; `callback_accounts` are passed on to
+// `callback_program` together with `callback_data`
+pub fn build_flashSwap_instruction(
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    swapper: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    amount0_out: &u64,
+    amount1_out: &u64,
+    callback_program: &Pubkey,
+    callback_data: &[u8],
+    callback_accounts: &[AccountMeta],
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("flash_swap");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&amount0_out.to_le_bytes());
+    instruction_data.extend_from_slice(&amount1_out.to_le_bytes());
+    instruction_data.extend_from_slice(&(callback_data.len() as u32).to_le_bytes());
+    instruction_data.extend_from_slice(callback_data);
+
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+    let (observations_pda, _bump_observations) =
+        get_utils::get_observations_pda(program_id, &pool_pda);
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let (pool_authority_pda, _bump_pool_authority) =
+        get_utils::get_pool_authority_pda(program_id, &pool_pda, token0, token1);
+    let token0_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token0, token0_program);
+    let token1_vault =
+        get_associated_token_address_with_program_id(&pool_authority_pda, token1, token1_program);
+    let swapper_token0_ata =
+        get_associated_token_address_with_program_id(swapper, token0, token0_program);
+    let swapper_token1_ata =
+        get_associated_token_address_with_program_id(swapper, token1, token1_program);
+
+    let mut accounts = vec![
+        AccountMeta::new(*swapper, true),
+        AccountMeta::new_readonly(*token0, false),
+        AccountMeta::new_readonly(*token1, false),
+        AccountMeta::new(pool_pda, false),
+        AccountMeta::new(observations_pda, false),
+        AccountMeta::new_readonly(admin_settings, false),
+        AccountMeta::new_readonly(pool_authority_pda, false),
+        AccountMeta::new(token0_vault, false),
+        AccountMeta::new(token1_vault, false),
+        AccountMeta::new(swapper_token0_ata, false),
+        AccountMeta::new(swapper_token1_ata, false),
+        AccountMeta::new_readonly(*callback_program, false),
+        AccountMeta::new_readonly(*token0_program, false),
+        AccountMeta::new_readonly(*token1_program, false),
+    ];
+    accounts.extend_from_slice(callback_accounts);
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: instruction_data,
+    }
+}
+
+// Same accounts as create_pool
+pub fn build_createStablePool_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    token0_allowed: &bool,
+    token1_allowed: &bool,
+    has_creator_role: &bool,
+    treasury: &Pubkey,
+    fee_bps: &u16,
+    amp: &u64,
+) -> Instruction {
+    let mut ix = build_createPool_instruction(
+        program_id,
+        admin,
+        token0,
+        token1,
+        token0_program,
+        token1_program,
+        token0_allowed,
+        token1_allowed,
+        has_creator_role,
+        treasury,
+        fee_bps,
+    );
+    let mut instruction_data = get_utils::get_discriminator("create_stable_pool").to_vec();
+    instruction_data.extend_from_slice(&amp.to_le_bytes());
+    ix.data = instruction_data;
+    ix
+}
+
+pub fn build_rampAmp_instruction(
+    program_id: &Pubkey,
+    fee_manager: &Pubkey,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    target_amp: &u64,
+    ramp_end: &i64,
+) -> Instruction {
+    let discriminator = get_utils::get_discriminator("ramp_amp");
+    let mut instruction_data = Vec::new();
+    instruction_data.extend_from_slice(&discriminator);
+    instruction_data.extend_from_slice(&target_amp.to_le_bytes());
+    instruction_data.extend_from_slice(&ramp_end.to_le_bytes());
+
+    let (fee_manager_role, _bump) = get_utils::get_role_pda(program_id, fee_manager);
+    let (pool_pda, _bump_pool) = get_utils::get_pool_pda(program_id, amm_config, token0, token1);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*fee_manager, true),
+            AccountMeta::new_readonly(fee_manager_role, false),
+            AccountMeta::new(pool_pda, false),
+        ],
+        data: instruction_data,
+    }
+}
+
+// Same accounts as create_pool
+pub fn build_createWeightedPool_instruction(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+    token0_allowed: &bool,
+    token1_allowed: &bool,
+    has_creator_role: &bool,
+    treasury: &Pubkey,
+    fee_bps: &u16,
+    weight0: &u16,
+) -> Instruction {
+    let mut ix = build_createPool_instruction(
+        program_id,
+        admin,
+        token0,
+        token1,
+        token0_program,
+        token1_program,
+        token0_allowed,
+        token1_allowed,
+        has_creator_role,
+        treasury,
+        fee_bps,
+    );
+    let mut instruction_data = get_utils::get_discriminator("create_weighted_pool").to_vec();
+    instruction_data.extend_from_slice(&weight0.to_le_bytes());
+    ix.data = instruction_data;
+    ix
+}
@@ -1,14 +1,27 @@
 use {
-    fixed::types::I64F64,
+    base64::{engine::general_purpose::STANDARD, Engine},
+    borsh::BorshDeserialize,
+    fixed::types::U128F0,
     sha2::{Digest, Sha256},
     solana_sdk::pubkey::Pubkey,
 };
 
-pub fn get_expected_liquidity(amount_a: &u64, amount_b: &u64) -> Result<u64, String> {
-    let a = I64F64::from_num(*amount_a);
-    let b = I64F64::from_num(*amount_b);
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+// RoleAssignment bits
+pub const POOL_CREATOR: u8 = 1 << 0;
+pub const FEE_MANAGER: u8 = 1 << 1;
+pub const PAUSER: u8 = 1 << 2;
+pub const TREASURY_WITHDRAWER: u8 = 1 << 3;
+
+pub const BPF_LOADER_UPGRADEABLE_ID: Pubkey =
+    Pubkey::from_str_const("BPFLoaderUpgradeab1e11111111111111111111111");
+
+pub const INSTRUCTIONS_SYSVAR_ID: Pubkey =
+    Pubkey::from_str_const("Sysvar1nstructions1111111111111111111111111");
 
-    let product = a.checked_mul(b).ok_or_else(|| "Overflow".to_string())?;
+pub fn get_expected_liquidity(amount_a: &u64, amount_b: &u64) -> Result<u64, String> {
+    let product = U128F0::from_num(*amount_a as u128 * *amount_b as u128);
 
     let sqrt_res = product.sqrt();
 
@@ -19,6 +32,118 @@ pub fn get_expected_liquidity(amount_a: &u64, amount_b: &u64) -> Result<u64, Str
     Ok(liq)
 }
 
+pub fn get_expected_amount_out(
+    amount_in: &u64,
+    reserve_in: &u64,
+    reserve_out: &u64,
+    fee_bps: &u16,
+) -> Result<u64, String> {
+    let amount_in_with_fee = *amount_in as u128 * (10_000 - *fee_bps as u128);
+    let numerator = amount_in_with_fee * *reserve_out as u128;
+    let denominator = *reserve_in as u128 * 10_000 + amount_in_with_fee;
+
+    u64::try_from(numerator / denominator).map_err(|_| "Result doesn't fit in u64".to_string())
+}
+
+pub fn get_expected_amount_in(
+    amount_out: &u64,
+    reserve_in: &u64,
+    reserve_out: &u64,
+    fee_bps: &u16,
+) -> Result<u64, String> {
+    let numerator = *reserve_in as u128 * *amount_out as u128 * 10_000;
+    let denominator = (*reserve_out - *amount_out) as u128 * (10_000 - *fee_bps as u128);
+
+    u64::try_from(numerator.div_ceil(denominator))
+        .map_err(|_| "Result doesn't fit in u64".to_string())
+}
+
+// StableSwap D for two balances, Newton's method as in the program
+pub fn get_expected_stable_d(amp: &u64, x: &u64, y: &u64) -> u128 {
+    let (x, y) = (*x as u128, *y as u128);
+    let ann = *amp as u128 * 4;
+    let mut d = x + y;
+    loop {
+        let d_p = d * d / (x * 2) * d / (y * 2);
+        let d_prev = d;
+        d = (ann * (x + y) + d_p * 2) * d / ((ann - 1) * d + 3 * d_p);
+        if d.abs_diff(d_prev) <= 1 {
+            return d;
+        }
+    }
+}
+
+pub fn get_expected_stable_amount_out(
+    amount_in: &u64,
+    reserve_in: &u64,
+    reserve_out: &u64,
+    fee_bps: &u16,
+    amp: &u64,
+) -> u64 {
+    let amount_in_with_fee = *amount_in as u128 * (10_000 - *fee_bps as u128) / 10_000;
+    let d = get_expected_stable_d(amp, reserve_in, reserve_out);
+
+    let ann = *amp as u128 * 4;
+    let x = *reserve_in as u128 + amount_in_with_fee;
+    let c = d * d / (x * 2) * d / (ann * 2);
+    let b = x + d / ann;
+    let mut y = d;
+    loop {
+        let y_prev = y;
+        y = (y * y + c) / (2 * y + b - d);
+        if y.abs_diff(y_prev) <= 1 {
+            break;
+        }
+    }
+
+    (*reserve_out as u128).saturating_sub(y + 1) as u64
+}
+
+// -dy/dx on the StableSwap curve, y * (Ann * x + P) / (x * (Ann * y + P))
+// with P = D^3 / (4 * x * y), in floating point
+pub fn get_expected_stable_spot_price(amp: &u64, x: &u64, y: &u64) -> f64 {
+    let d = get_expected_stable_d(amp, x, y) as f64;
+    let (x, y) = (*x as f64, *y as f64);
+    let ann = *amp as f64 * 4.0;
+    let d_p = d * d * d / (4.0 * x * y);
+
+    y * (ann * x + d_p) / (x * (ann * y + d_p))
+}
+
+// Weighted product output in floating point; the program's fixed point
+// result is at most a unit below it
+pub fn get_expected_weighted_amount_out(
+    amount_in: &u64,
+    reserve_in: &u64,
+    reserve_out: &u64,
+    fee_bps: &u16,
+    weight_in: &u16,
+    weight_out: &u16,
+) -> f64 {
+    let amount_in_with_fee = (*amount_in as u128 * (10_000 - *fee_bps as u128) / 10_000) as f64;
+    let base = *reserve_in as f64 / (*reserve_in as f64 + amount_in_with_fee);
+
+    *reserve_out as f64 * (1.0 - base.powf(*weight_in as f64 / *weight_out as f64))
+}
+
+// Flash loan fee, charged at the pool fee rate and rounded up
+pub fn get_expected_flash_loan_fee(amount: &u64, fee_bps: &u16) -> u64 {
+    (*amount as u128 * *fee_bps as u128).div_ceil(10_000) as u64
+}
+
+// Fee withheld by a Token-2022 TransferFeeConfig, rounded up like the token program does
+pub fn get_expected_transfer_fee(amount: &u64, transfer_fee_bps: &u16, maximum_fee: &u64) -> u64 {
+    let fee = (*amount as u128 * *transfer_fee_bps as u128).div_ceil(10_000) as u64;
+
+    fee.min(*maximum_fee)
+}
+
+// Reads the balance of an SPL Token or Token-2022 account; both share the
+// base layout, with the amount stored after the mint and owner
+pub fn get_token_balance(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[64..72].try_into().expect("failed to read amount"))
+}
+
 pub fn get_discriminator(instruction_name: &str) -> [u8; 8] {
     let mut hasher = Sha256::new();
     hasher.update(format!("global:{}", instruction_name));
@@ -28,17 +153,54 @@ pub fn get_discriminator(instruction_name: &str) -> [u8; 8] {
     discriminator
 }
 
+pub fn get_event_discriminator(event_name: &str) -> [u8; 8] {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("event:{}", event_name));
+    let result = hasher.finalize();
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&result[..8]);
+    discriminator
+}
+
+// Decodes every `event_name` event emit! wrote to the logs as "Program data: <base64>"
+pub fn get_events<T: BorshDeserialize>(logs: &[String], event_name: &str) -> Vec<T> {
+    let discriminator = get_event_discriminator(event_name);
+
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter(|data| data.starts_with(&discriminator))
+        .map(|data| T::deserialize(&mut &data[8..]).expect("Failed to deserialize event"))
+        .collect()
+}
+
+pub fn get_program_data_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[program_id.as_ref()], &BPF_LOADER_UPGRADEABLE_ID)
+}
+
 pub fn get_admin_settings_pda(program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"admin"], program_id)
 }
 
+pub fn get_role_pda(program_id: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"role", holder.as_ref()], program_id)
+}
+
+pub fn get_allowed_mint_pda(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"allowed_mint", mint.as_ref()], program_id)
+}
+
+pub fn get_amm_config_pda(program_id: &Pubkey, fee_bps: &u16) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"amm_config", &fee_bps.to_le_bytes()], program_id)
+}
+
 pub fn get_tokenliq_pda(
     program_id: &Pubkey,
-    admin_pub: &Pubkey,
+    amm_config: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
 ) -> (Pubkey, u8) {
-    let (pool_pda, _bump) = get_pool_pda(&program_id, &admin_pub, &token0, &token1);
+    let (pool_pda, _bump) = get_pool_pda(&program_id, &amm_config, &token0, &token1);
 
     Pubkey::find_program_address(
         &[
@@ -61,11 +223,23 @@ pub fn get_token_vault_pda(
 
 pub fn get_pool_pda(
     program_id: &Pubkey,
-    admin_pub: &Pubkey,
+    amm_config: &Pubkey,
     token0: &Pubkey,
     token1: &Pubkey,
 ) -> (Pubkey, u8) {
-    Pubkey::find_program_address(&[b"pool", token0.as_ref(), token1.as_ref()], program_id)
+    Pubkey::find_program_address(
+        &[
+            b"pool",
+            amm_config.as_ref(),
+            token0.as_ref(),
+            token1.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+pub fn get_observations_pda(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"observations", pool.as_ref()], program_id)
 }
 
 pub fn get_pool_authority_pda(
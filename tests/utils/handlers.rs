@@ -1,6 +1,7 @@
 use {
     litesvm::{types::TransactionResult, LiteSVM},
     solana_sdk::{
+        instruction::{AccountMeta, Instruction},
         pubkey::Pubkey,
         signature::{Keypair, Signer},
         transaction::Transaction,
@@ -10,6 +11,40 @@ use {
 use crate::utils::build_utils;
 use crate::utils::get_utils;
 
+// Token program owning the mint, so the same handlers drive SPL Token and Token-2022 pools
+// Whether the admin has allowlisted `mint`, i.e. its AllowedMint account exists
+pub fn is_mint_allowed(svm: &LiteSVM, program_id: &Pubkey, mint: &Pubkey) -> bool {
+    let (allowed_mint, _bump) = get_utils::get_allowed_mint_pda(program_id, mint);
+    svm.get_account(&allowed_mint)
+        .is_some_and(|account| account.lamports > 0)
+}
+
+// AdminSettings.treasury, read from its offset past the discriminator and the
+// admin, protocol_fee_bps, paused, pending_admin, permissionless and fee fields
+pub fn get_admin(svm: &LiteSVM, program_id: &Pubkey) -> Pubkey {
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let data = svm
+        .get_account(&admin_settings)
+        .expect("Admin settings should exist")
+        .data;
+
+    Pubkey::try_from(&data[8..40]).unwrap()
+}
+
+pub fn get_treasury(svm: &LiteSVM, program_id: &Pubkey) -> Pubkey {
+    let (admin_settings, _bump) = get_utils::get_admin_settings_pda(program_id);
+    let data = svm
+        .get_account(&admin_settings)
+        .expect("Admin settings should exist")
+        .data;
+
+    Pubkey::try_from(&data[84..116]).unwrap()
+}
+
+pub fn get_token_program(svm: &LiteSVM, mint: &Pubkey) -> Pubkey {
+    svm.get_account(mint).expect("Mint should exist").owner
+}
+
 pub fn handle_init_admin(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
@@ -27,14 +62,53 @@ pub fn handle_init_admin(
     tx_result
 }
 
-pub fn handle_set_admin(
+pub fn handle_propose_admin(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
     new_admin: &Keypair,
     admin: &Keypair,
 ) -> TransactionResult {
+    let ix = build_utils::build_proposeAdmin_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &new_admin.pubkey(),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_accept_admin(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    pending_admin: &Keypair,
+) -> TransactionResult {
+    let admin = get_admin(svm, program_id);
     let ix =
-        build_utils::build_setAdmin_instruction(&program_id, &admin.pubkey(), &new_admin.pubkey());
+        build_utils::build_acceptAdmin_instruction(&program_id, &admin, &pending_admin.pubkey());
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&pending_admin.pubkey()),
+        &[&pending_admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_cancel_admin_proposal(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+) -> TransactionResult {
+    let ix = build_utils::build_cancelAdminProposal_instruction(&program_id, &admin.pubkey());
     let tx = Transaction::new_signed_with_payer(
         &[ix],
         Some(&admin.pubkey()),
@@ -46,15 +120,250 @@ pub fn handle_set_admin(
     tx_result
 }
 
+pub fn handle_grant_role(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    holder: &Pubkey,
+    roles: &u8,
+) -> TransactionResult {
+    let ix =
+        build_utils::build_grantRole_instruction(&program_id, &admin.pubkey(), &holder, &roles);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_revoke_role(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    holder: &Pubkey,
+    roles: &u8,
+) -> TransactionResult {
+    let ix =
+        build_utils::build_revokeRole_instruction(&program_id, &admin.pubkey(), &holder, &roles);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_set_protocol_fee(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    protocol_fee_bps: &u16,
+) -> TransactionResult {
+    let ix = build_utils::build_setProtocolFee_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &protocol_fee_bps,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_set_global_pause(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    paused: &bool,
+) -> TransactionResult {
+    let ix = build_utils::build_setGlobalPause_instruction(&program_id, &admin.pubkey(), &paused);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_set_pool_status(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    status: &u8,
+) -> TransactionResult {
+    let ix = build_utils::build_setPoolStatus_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &amm_config,
+        &token0,
+        &token1,
+        &status,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_set_pool_creation_config(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    permissionless: &bool,
+    creation_fee: &u64,
+    treasury: &Pubkey,
+) -> TransactionResult {
+    let ix = build_utils::build_setPoolCreationConfig_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &permissionless,
+        &creation_fee,
+        &treasury,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_delist_pool(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> TransactionResult {
+    let ix = build_utils::build_delistPool_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &amm_config,
+        &token0,
+        &token1,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_collect_protocol_fees(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    treasury_account_0: &Pubkey,
+    treasury_account_1: &Pubkey,
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let ix = build_utils::build_collectProtocolFees_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &amm_config,
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &treasury_account_0,
+        &treasury_account_1,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_create_amm_config(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    fee_manager: &Keypair,
+    fee_bps: &u16,
+) -> TransactionResult {
+    let ix = build_utils::build_createAmmConfig_instruction(
+        &program_id,
+        &fee_manager.pubkey(),
+        &fee_bps,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&fee_manager.pubkey()),
+        &[&fee_manager],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
 pub fn handle_create_pool(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
     admin: &Keypair,
     token0: &Pubkey,
     token1: &Pubkey,
+    fee_bps: &u16,
 ) -> TransactionResult {
-    let ix =
-        build_utils::build_createPool_instruction(&program_id, &admin.pubkey(), &token0, &token1);
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let token0_allowed = is_mint_allowed(svm, program_id, token0);
+    let token1_allowed = is_mint_allowed(svm, program_id, token1);
+    let (creator_role, _bump) = get_utils::get_role_pda(program_id, &admin.pubkey());
+    let has_creator_role = svm.get_account(&creator_role).is_some();
+    let treasury = get_treasury(svm, program_id);
+    let ix = build_utils::build_createPool_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &token0_allowed,
+        &token1_allowed,
+        &has_creator_role,
+        &treasury,
+        &fee_bps,
+    );
     let tx = Transaction::new_signed_with_payer(
         &[ix],
         Some(&admin.pubkey()),
@@ -66,24 +375,178 @@ pub fn handle_create_pool(
     tx_result
 }
 
-pub fn handle_deposit(
+pub fn handle_create_stable_pool(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
     admin: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    fee_bps: &u16,
+    amp: &u64,
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let token0_allowed = is_mint_allowed(svm, program_id, token0);
+    let token1_allowed = is_mint_allowed(svm, program_id, token1);
+    let (creator_role, _bump) = get_utils::get_role_pda(program_id, &admin.pubkey());
+    let has_creator_role = svm.get_account(&creator_role).is_some();
+    let treasury = get_treasury(svm, program_id);
+    let ix = build_utils::build_createStablePool_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &token0_allowed,
+        &token1_allowed,
+        &has_creator_role,
+        &treasury,
+        &fee_bps,
+        &amp,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_create_weighted_pool(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    fee_bps: &u16,
+    weight0: &u16,
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let token0_allowed = is_mint_allowed(svm, program_id, token0);
+    let token1_allowed = is_mint_allowed(svm, program_id, token1);
+    let (creator_role, _bump) = get_utils::get_role_pda(program_id, &admin.pubkey());
+    let has_creator_role = svm.get_account(&creator_role).is_some();
+    let treasury = get_treasury(svm, program_id);
+    let ix = build_utils::build_createWeightedPool_instruction(
+        &program_id,
+        &admin.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &token0_allowed,
+        &token1_allowed,
+        &has_creator_role,
+        &treasury,
+        &fee_bps,
+        &weight0,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_ramp_amp(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    fee_manager: &Keypair,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    target_amp: &u64,
+    ramp_end: &i64,
+) -> TransactionResult {
+    let ix = build_utils::build_rampAmp_instruction(
+        &program_id,
+        &fee_manager.pubkey(),
+        &amm_config,
+        &token0,
+        &token1,
+        &target_amp,
+        &ramp_end,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&fee_manager.pubkey()),
+        &[&fee_manager],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_allow_mint(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    mint: &Pubkey,
+) -> TransactionResult {
+    let ix = build_utils::build_allowMint_instruction(&program_id, &admin.pubkey(), &mint);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_disallow_mint(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    mint: &Pubkey,
+) -> TransactionResult {
+    let ix = build_utils::build_disallowMint_instruction(&program_id, &admin.pubkey(), &mint);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&admin.pubkey()),
+        &[&admin],
+        svm.latest_blockhash(),
+    );
+
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_deposit(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
     depositor: &Keypair,
     token0: &Pubkey,
     token1: &Pubkey,
     amount_a: &u64,
     amount_b: &u64,
+    min_liquidity_out: &u64,
 ) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
     let ix = build_utils::build_deposit_instruction(
         &program_id,
-        &admin.pubkey(),
+        &amm_config,
         &depositor.pubkey(),
         &token0,
         &token1,
+        &token0_program,
+        &token1_program,
         &amount_a,
         &amount_b,
+        &min_liquidity_out,
     );
     let tx = Transaction::new_signed_with_payer(
         &[ix],
@@ -99,19 +562,27 @@ pub fn handle_deposit(
 pub fn handle_withdraw(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
-    admin: &Keypair,
+    amm_config: &Pubkey,
     depositor: &Keypair,
     token0: &Pubkey,
     token1: &Pubkey,
     amount: &u64,
+    min_amount0_out: &u64,
+    min_amount1_out: &u64,
 ) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
     let ix = build_utils::build_withdraw_instruction(
         &program_id,
-        &admin.pubkey(),
+        &amm_config,
         &depositor.pubkey(),
         &token0,
         &token1,
+        &token0_program,
+        &token1_program,
         &amount,
+        &min_amount0_out,
+        &min_amount1_out,
     );
     let tx = Transaction::new_signed_with_payer(
         &[ix],
@@ -126,21 +597,263 @@ pub fn handle_withdraw(
 pub fn handle_swapExactInput(
     svm: &mut LiteSVM,
     program_id: &Pubkey,
-    admin: &Keypair,
+    amm_config: &Pubkey,
     swapper: &Keypair,
     token0: &Pubkey,
     token1: &Pubkey,
     token_in: &Pubkey,
     amount: &u64,
+    minimum_amount_out: &u64,
 ) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
     let ix = build_utils::build_swapExactInput_instruction(
         &program_id,
-        &admin.pubkey(),
+        &amm_config,
         &swapper.pubkey(),
         &token0,
         &token1,
+        &token0_program,
+        &token1_program,
         &token_in,
         &amount,
+        &minimum_amount_out,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&swapper.pubkey()),
+        &[&swapper],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_swapExactOutput(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    swapper: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    token_in: &Pubkey,
+    amount_out: &u64,
+    maximum_amount_in: &u64,
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let ix = build_utils::build_swapExactOutput_instruction(
+        &program_id,
+        &amm_config,
+        &swapper.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &token_in,
+        &amount_out,
+        &maximum_amount_in,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&swapper.pubkey()),
+        &[&swapper],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+// `payer` only pays the fee; the result is in the transaction's return data
+pub fn handle_observe(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    payer: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) -> TransactionResult {
+    let ix = build_utils::build_observe_instruction(&program_id, &amm_config, &token0, &token1);
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_get_twap(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    payer: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    start_timestamp: &i64,
+    end_timestamp: &i64,
+) -> TransactionResult {
+    let ix = build_utils::build_getTwap_instruction(
+        &program_id,
+        &amm_config,
+        &token0,
+        &token1,
+        &start_timestamp,
+        &end_timestamp,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_grow_observations(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    amm_config: &Pubkey,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    new_capacity: &u16,
+) -> TransactionResult {
+    let ix = build_utils::build_growObservations_instruction(
+        &program_id,
+        &payer.pubkey(),
+        &amm_config,
+        &token0,
+        &token1,
+        &new_capacity,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&payer.pubkey()),
+        &[&payer],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+// `hops` holds (amm_config, token0, token1) of each pool on the route
+pub fn handle_swap_route(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    swapper: &Keypair,
+    user_source: &Pubkey,
+    user_destination: &Pubkey,
+    hops: &[(Pubkey, Pubkey, Pubkey)],
+    amount_in: &u64,
+    minimum_amount_out: &u64,
+) -> TransactionResult {
+    let hops: Vec<_> = hops
+        .iter()
+        .map(|(amm_config, token0, token1)| {
+            (
+                *amm_config,
+                *token0,
+                *token1,
+                get_token_program(svm, token0),
+                get_token_program(svm, token1),
+            )
+        })
+        .collect();
+    let ix = build_utils::build_swapRoute_instruction(
+        &program_id,
+        &swapper.pubkey(),
+        &user_source,
+        &user_destination,
+        &hops,
+        &amount_in,
+        &minimum_amount_out,
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&swapper.pubkey()),
+        &[&swapper],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+// Sends flash_borrow, then `instructions`, then flash_repay in one transaction
+pub fn handle_flash_loan(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    borrower: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    amount0: &u64,
+    amount1: &u64,
+    instructions: &[Instruction],
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let mut ixs = vec![build_utils::build_flashBorrow_instruction(
+        &program_id,
+        &amm_config,
+        &borrower.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &amount0,
+        &amount1,
+    )];
+    ixs.extend_from_slice(instructions);
+    ixs.push(build_utils::build_flashRepay_instruction(
+        &program_id,
+        &amm_config,
+        &borrower.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+    ));
+    let tx = Transaction::new_signed_with_payer(
+        &ixs,
+        Some(&borrower.pubkey()),
+        &[&borrower],
+        svm.latest_blockhash(),
+    );
+    let tx_result = svm.send_transaction(tx);
+    tx_result
+}
+
+pub fn handle_flash_swap(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    amm_config: &Pubkey,
+    swapper: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    amount0_out: &u64,
+    amount1_out: &u64,
+    callback_program: &Pubkey,
+    callback_data: &[u8],
+    callback_accounts: &[AccountMeta],
+) -> TransactionResult {
+    let token0_program = get_token_program(svm, token0);
+    let token1_program = get_token_program(svm, token1);
+    let ix = build_utils::build_flashSwap_instruction(
+        &program_id,
+        &amm_config,
+        &swapper.pubkey(),
+        &token0,
+        &token1,
+        &token0_program,
+        &token1_program,
+        &amount0_out,
+        &amount1_out,
+        &callback_program,
+        &callback_data,
+        &callback_accounts,
     );
     let tx = Transaction::new_signed_with_payer(
         &[ix],
@@ -1,3 +1,4 @@
 pub mod build_utils;
 pub mod get_utils;
 pub mod handlers;
+pub mod setup_utils;
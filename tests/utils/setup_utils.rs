@@ -0,0 +1,396 @@
+use {
+    litesvm::LiteSVM,
+    litesvm_token::{
+        spl_token::native_mint::DECIMALS, CreateAssociatedTokenAccount, CreateMint, MintTo,
+    },
+    solana_sdk::{
+        account::Account,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    },
+    solana_system_interface::instruction::create_account,
+    spl_token_2022_interface::{
+        extension::{transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType},
+        instruction::initialize_mint2,
+        state::Mint,
+    },
+};
+
+use crate::utils::get_utils;
+use crate::utils::handlers;
+
+// Deploys the program through the upgradeable loader, the way `solana program deploy`
+// does, so that init_admin can check `upgrade_authority` against its ProgramData
+pub fn deploy_program(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    program_bytes: &[u8],
+    upgrade_authority: &Pubkey,
+) {
+    let (program_data, _bump) = get_utils::get_program_data_address(program_id);
+
+    // UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address } + ELF
+    let mut program_data_bytes = Vec::new();
+    program_data_bytes.extend_from_slice(&3u32.to_le_bytes());
+    program_data_bytes.extend_from_slice(&0u64.to_le_bytes());
+    program_data_bytes.push(1);
+    program_data_bytes.extend_from_slice(upgrade_authority.as_ref());
+    program_data_bytes.extend_from_slice(program_bytes);
+
+    // UpgradeableLoaderState::Program { programdata_address }
+    let mut program_account_bytes = Vec::new();
+    program_account_bytes.extend_from_slice(&2u32.to_le_bytes());
+    program_account_bytes.extend_from_slice(program_data.as_ref());
+
+    svm.set_account(
+        program_data,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(program_data_bytes.len()),
+            data: program_data_bytes,
+            owner: get_utils::BPF_LOADER_UPGRADEABLE_ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+
+    svm.set_account(
+        *program_id,
+        Account {
+            lamports: svm.minimum_balance_for_rent_exemption(program_account_bytes.len()),
+            data: program_account_bytes,
+            owner: get_utils::BPF_LOADER_UPGRADEABLE_ID,
+            executable: true,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+// Initializes admin, creates the fee_bps tier and a pool with fresh mints and funds the depositor
+// with `amount_a` / `amount_b` of them. Returns (token0, token1).
+pub fn setup_pool(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    depositor: &Keypair,
+    fee_bps: &u16,
+    amount_a: &u64,
+    amount_b: &u64,
+) -> (Pubkey, Pubkey) {
+    setup_pool_with_mints(
+        svm,
+        program_id,
+        admin,
+        depositor,
+        fee_bps,
+        amount_a,
+        amount_b,
+        &DECIMALS,
+        &DECIMALS,
+        &spl_token::id(),
+        &spl_token::id(),
+    )
+}
+
+// setup_pool followed by a deposit of the whole `amount_a` / `amount_b`
+pub fn setup_pool_with_liquidity(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    depositor: &Keypair,
+    fee_bps: &u16,
+    amount_a: &u64,
+    amount_b: &u64,
+) -> (Pubkey, Pubkey) {
+    let (token0, token1) = setup_pool(
+        svm, program_id, admin, depositor, fee_bps, amount_a, amount_b,
+    );
+    let (amm_config, _bump) = get_utils::get_amm_config_pda(program_id, fee_bps);
+
+    let tx_result = handlers::handle_deposit(
+        svm,
+        program_id,
+        &amm_config,
+        depositor,
+        &token0,
+        &token1,
+        amount_a,
+        amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    (token0, token1)
+}
+
+// Same as setup_pool, with the decimals and token program of each mint chosen by the caller
+pub fn setup_pool_with_mints(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    depositor: &Keypair,
+    fee_bps: &u16,
+    amount_a: &u64,
+    amount_b: &u64,
+    decimals0: &u8,
+    decimals1: &u8,
+    token0_program: &Pubkey,
+    token1_program: &Pubkey,
+) -> (Pubkey, Pubkey) {
+    let token0 = CreateMint::new(svm, admin)
+        .authority(&admin.pubkey())
+        .decimals(*decimals0)
+        .token_program_id(token0_program)
+        .send()
+        .unwrap();
+
+    let mut token1 = CreateMint::new(svm, admin)
+        .authority(&admin.pubkey())
+        .decimals(*decimals1)
+        .token_program_id(token1_program)
+        .send()
+        .unwrap();
+
+    while token1 <= token0 {
+        token1 = CreateMint::new(svm, admin)
+            .authority(&admin.pubkey())
+            .decimals(*decimals1)
+            .token_program_id(token1_program)
+            .send()
+            .unwrap();
+    }
+
+    setup_pool_for_mints(
+        svm, program_id, admin, depositor, fee_bps, amount_a, amount_b, &token0, &token1,
+    );
+
+    (token0, token1)
+}
+
+// Initializes admin, creates the fee_bps tier and a pool for existing mints (token0 < token1, both
+// with admin as mint authority) and funds the depositor
+pub fn setup_pool_for_mints(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    depositor: &Keypair,
+    fee_bps: &u16,
+    amount_a: &u64,
+    amount_b: &u64,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) {
+    let tx_result = handlers::handle_init_admin(svm, program_id, admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let tx_result = handlers::handle_create_amm_config(svm, program_id, admin, fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let tx_result = handlers::handle_create_pool(svm, program_id, admin, token0, token1, fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let (amm_config, _bump) = get_utils::get_amm_config_pda(program_id, fee_bps);
+    fund_depositor(
+        svm,
+        program_id,
+        admin,
+        depositor,
+        &amm_config,
+        amount_a,
+        amount_b,
+        token0,
+        token1,
+    );
+}
+
+// Creates the depositor's token and LP accounts for an existing pool and mints `amount_a` /
+// `amount_b` to them
+pub fn fund_depositor(
+    svm: &mut LiteSVM,
+    program_id: &Pubkey,
+    admin: &Keypair,
+    depositor: &Keypair,
+    amm_config: &Pubkey,
+    amount_a: &u64,
+    amount_b: &u64,
+    token0: &Pubkey,
+    token1: &Pubkey,
+) {
+    let token0_program = &handlers::get_token_program(svm, token0);
+    let token1_program = &handlers::get_token_program(svm, token1);
+    let (tokenliq_pda, _tokenliq_bump) =
+        get_utils::get_tokenliq_pda(program_id, amm_config, token0, token1);
+
+    let depositor_token0_ata_account = CreateAssociatedTokenAccount::new(svm, admin, token0)
+        .owner(&depositor.pubkey())
+        .token_program_id(token0_program)
+        .send()
+        .unwrap();
+
+    let depositor_token1_ata_account = CreateAssociatedTokenAccount::new(svm, admin, token1)
+        .owner(&depositor.pubkey())
+        .token_program_id(token1_program)
+        .send()
+        .unwrap();
+
+    CreateAssociatedTokenAccount::new(svm, admin, &tokenliq_pda)
+        .owner(&depositor.pubkey())
+        .send()
+        .unwrap();
+
+    MintTo::new(svm, admin, token0, &depositor_token0_ata_account, *amount_a)
+        .token_program_id(token0_program)
+        .send()
+        .unwrap();
+
+    MintTo::new(svm, admin, token1, &depositor_token1_ata_account, *amount_b)
+        .token_program_id(token1_program)
+        .send()
+        .unwrap();
+}
+
+// Creates the swapper's token accounts and mints `amount` of both tokens to them
+pub fn fund_swapper(
+    svm: &mut LiteSVM,
+    admin: &Keypair,
+    swapper: &Keypair,
+    token0: &Pubkey,
+    token1: &Pubkey,
+    amount: &u64,
+) {
+    for token in [token0, token1] {
+        let swapper_ata = CreateAssociatedTokenAccount::new(svm, admin, token)
+            .owner(&swapper.pubkey())
+            .send()
+            .unwrap();
+
+        MintTo::new(svm, admin, token, &swapper_ata, *amount)
+            .send()
+            .unwrap();
+    }
+}
+
+// Two fresh SPL mints with admin as authority, ordered so token0 < token1
+pub fn create_mints(
+    svm: &mut LiteSVM,
+    admin: &Keypair,
+    decimals0: &u8,
+    decimals1: &u8,
+) -> (Pubkey, Pubkey) {
+    let token0 = CreateMint::new(svm, admin)
+        .authority(&admin.pubkey())
+        .decimals(*decimals0)
+        .send()
+        .unwrap();
+
+    let mut token1 = CreateMint::new(svm, admin)
+        .authority(&admin.pubkey())
+        .decimals(*decimals1)
+        .send()
+        .unwrap();
+
+    while token1 <= token0 {
+        token1 = CreateMint::new(svm, admin)
+            .authority(&admin.pubkey())
+            .decimals(*decimals1)
+            .send()
+            .unwrap();
+    }
+
+    (token0, token1)
+}
+
+// Token-2022 mint with a TransferFeeConfig extension charging `transfer_fee_bps`
+// capped at `maximum_fee`, with `authority` as mint authority
+pub fn create_transfer_fee_mint(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    authority: &Pubkey,
+    decimals: &u8,
+    transfer_fee_bps: &u16,
+    maximum_fee: &u64,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let init_transfer_fee = initialize_transfer_fee_config(
+        &spl_token_2022_interface::id(),
+        &mint.pubkey(),
+        None,
+        None,
+        *transfer_fee_bps,
+        *maximum_fee,
+    )
+    .unwrap();
+
+    create_token_2022_mint(
+        svm,
+        payer,
+        &mint,
+        authority,
+        decimals,
+        &[ExtensionType::TransferFeeConfig],
+        &[init_transfer_fee],
+    )
+}
+
+// Token-2022 mint with the given extensions, set up by `extension_instructions`
+// before the mint itself is initialized, with `authority` as mint authority
+pub fn create_token_2022_mint(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    mint: &Keypair,
+    authority: &Pubkey,
+    decimals: &u8,
+    extensions: &[ExtensionType],
+    extension_instructions: &[Instruction],
+) -> Pubkey {
+    let space = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let rent = svm.minimum_balance_for_rent_exemption(space);
+
+    let mut instructions = vec![create_account(
+        &payer.pubkey(),
+        &mint.pubkey(),
+        rent,
+        space as u64,
+        &spl_token_2022_interface::id(),
+    )];
+    instructions.extend_from_slice(extension_instructions);
+    instructions.push(
+        initialize_mint2(
+            &spl_token_2022_interface::id(),
+            &mint.pubkey(),
+            authority,
+            None,
+            *decimals,
+        )
+        .unwrap(),
+    );
+
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, mint],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap();
+
+    mint.pubkey()
+}
@@ -0,0 +1,246 @@
+use {
+    borsh::BorshDeserialize,
+    litesvm::LiteSVM,
+    litesvm_token::spl_token::native_mint::DECIMALS,
+    solana_sdk::{
+        instruction::InstructionError,
+        pubkey::Pubkey,
+        signature::{read_keypair_file, Keypair, Signer},
+        transaction::TransactionError,
+    },
+    spl_associated_token_account::get_associated_token_address,
+};
+
+mod utils;
+
+#[derive(Debug, BorshDeserialize)]
+struct PoolCreated {
+    pub pool: Pubkey,
+    pub creator: Pubkey,
+    pub amm_config: Pubkey,
+    pub token0: Pubkey,
+    pub token1: Pubkey,
+    pub fee_bps: u16,
+    pub creation_fee: u64,
+    // CurveType variant index
+    pub curve_type: u8,
+    pub weight0: u16,
+    pub weight1: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct Swapped {
+    pub pool: Pubkey,
+    pub swapper: Pubkey,
+    pub token_in: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub amount_out_received: u64,
+    pub swap_fee: u64,
+    pub protocol_fee: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
+}
+
+#[test]
+fn test_weighted_pool() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+    let alice = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+    svm.airdrop(&alice.pubkey(), 1_000_000_000).unwrap();
+
+    let tx_result = utils::handlers::handle_init_admin(&mut svm, &program_id, &admin);
+    assert!(
+        tx_result.is_ok(),
+        "Create transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let (token0, token1) = utils::setup_utils::create_mints(&mut svm, &admin, &DECIMALS, &DECIMALS);
+
+    // each weight has to be at least 1%
+    for weight0 in [0, 99, 9_901] {
+        let tx_result = utils::handlers::handle_create_weighted_pool(
+            &mut svm,
+            &program_id,
+            &admin,
+            &token0,
+            &token1,
+            &fee_bps,
+            &weight0,
+        );
+        match tx_result.unwrap_err().err {
+            // CustomError::InvalidWeight
+            TransactionError::InstructionError(0, InstructionError::Custom(6033)) => {}
+            err => panic!("Got unexpected error: {:?}", err),
+        }
+    }
+
+    // 80/20
+    let weight0 = 8_000;
+    let weight1 = 2_000;
+    let tx_result = utils::handlers::handle_create_weighted_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+        &weight0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Create weighted pool transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let (pool_pda, _bump) =
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
+    let events: Vec<PoolCreated> =
+        utils::get_utils::get_events(&tx_result.unwrap().logs, "PoolCreated");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].curve_type, 2);
+    assert_eq!(events[0].weight0, weight0);
+    assert_eq!(events[0].weight1, weight1);
+
+    // reserves in the ratio of the weights put the spot price at 1
+    let amount_a = 800_000;
+    let amount_b = 200_000;
+    utils::setup_utils::fund_depositor(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &amm_config,
+        &amount_a,
+        &amount_b,
+        &token0,
+        &token1,
+    );
+    let tx_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_result.err()
+    );
+
+    // first deposit mints a^0.8 * b^0.2, rounded down
+    let expected_liquidity = (amount_a as f64).powf(0.8) * (amount_b as f64).powf(0.2);
+    let (tokenliq_pda, _bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let depositor_lp_ata = get_associated_token_address(&depositor.pubkey(), &tokenliq_pda);
+    let liquidity =
+        utils::get_utils::get_token_balance(&svm.get_account(&depositor_lp_ata).unwrap().data)
+            + utils::get_utils::MINIMUM_LIQUIDITY;
+    assert!(
+        (expected_liquidity - 1.0..=expected_liquidity).contains(&(liquidity as f64)),
+        "liquidity {} expected {}",
+        liquidity,
+        expected_liquidity
+    );
+
+    let alice_funds = 100_000;
+    utils::setup_utils::fund_swapper(&mut svm, &admin, &alice, &token0, &token1, &alice_funds);
+
+    // token0 is the 80% side
+    let amount_in = 10_000;
+    let expected_amount_out = utils::get_utils::get_expected_weighted_amount_out(
+        &amount_in, &amount_a, &amount_b, &fee_bps, &weight0, &weight1,
+    );
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token0,
+        &amount_in,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].token_in, token0);
+    let amount_out = events[0].amount_out as f64;
+    assert!(
+        amount_out <= expected_amount_out && amount_out > expected_amount_out - 1.0,
+        "amount out {} expected {}",
+        amount_out,
+        expected_amount_out
+    );
+    assert_eq!(events[0].reserve0, amount_a + amount_in);
+    assert_eq!(events[0].reserve1, amount_b - events[0].amount_out);
+    let (reserve0, reserve1) = (events[0].reserve0, events[0].reserve1);
+
+    // and back the other way, with the weights swapped around
+    let expected_amount_out = utils::get_utils::get_expected_weighted_amount_out(
+        &amount_in, &reserve1, &reserve0, &fee_bps, &weight1, &weight0,
+    );
+    let tx_result = utils::handlers::handle_swapExactInput(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &alice,
+        &token0,
+        &token1,
+        &token1,
+        &amount_in,
+        &0,
+    );
+    assert!(
+        tx_result.is_ok(),
+        "Swap transaction failed: {:?}",
+        tx_result.as_ref().err()
+    );
+
+    let events: Vec<Swapped> = utils::get_utils::get_events(&tx_result.unwrap().logs, "Swapped");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].token_in, token1);
+    let amount_out = events[0].amount_out as f64;
+    assert!(
+        amount_out <= expected_amount_out && amount_out > expected_amount_out - 1.0,
+        "amount out {} expected {}",
+        amount_out,
+        expected_amount_out
+    );
+    assert_eq!(events[0].reserve0, reserve0 - events[0].amount_out);
+    assert_eq!(events[0].reserve1, reserve1 + amount_in);
+}
@@ -8,7 +8,7 @@ use {
     },
     solana_program::program_pack::Pack,
     solana_sdk::{
-        instruction::{AccountMeta, Instruction},
+        instruction::{AccountMeta, Instruction, InstructionError},
         pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
         transaction::{Transaction, TransactionError},
@@ -27,9 +27,24 @@ struct AdminSettings {
 
 #[derive(Debug, BorshDeserialize)]
 struct Pool {
+    pub amm_config: Pubkey,
     pub token0: Pubkey,
     pub token1: Pubkey,
     pub liquidity: u64,
+    pub fee_bps: u16,
+}
+
+#[derive(Debug, BorshDeserialize)]
+struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount0: u64,
+    pub amount1: u64,
+    pub amount0_received: u64,
+    pub amount1_received: u64,
+    pub liquidity_burned: u64,
+    pub reserve0: u64,
+    pub reserve1: u64,
 }
 
 #[test]
@@ -42,7 +57,7 @@ fn test_successful_withdraw() {
         .expect("Failed to read program keypair");
     let program_id = program_keypair.pubkey();
     let program_bytes = include_bytes!("../target/deploy/amm.so");
-    svm.add_program(program_id, program_bytes);
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
 
     svm.airdrop(&admin.pubkey(), 1_000_000_000).unwrap();
     svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
@@ -76,8 +91,24 @@ fn test_successful_withdraw() {
     }
 
     // Creating pool
-    let tx_createPool_result =
-        utils::handlers::handle_create_pool(&mut svm, &program_id, &admin, &token0, &token1);
+    let fee_bps = 30;
+    let tx_result =
+        utils::handlers::handle_create_amm_config(&mut svm, &program_id, &admin, &fee_bps);
+    assert!(
+        tx_result.is_ok(),
+        "Create amm config transaction failed: {:?}",
+        tx_result.err()
+    );
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+
+    let tx_createPool_result = utils::handlers::handle_create_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &token0,
+        &token1,
+        &fee_bps,
+    );
     assert!(
         tx_createPool_result.is_ok(),
         "Create Pool tx failed: {:?}",
@@ -85,18 +116,18 @@ fn test_successful_withdraw() {
     );
 
     let (pool_pda, _bump_pool_pda) =
-        utils::get_utils::get_pool_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_pool_pda(&program_id, &amm_config, &token0, &token1);
     let account_pool = svm.get_account(&pool_pda).expect("Should exist");
 
     let data = Pool::deserialize(&mut &account_pool.data[8..]).expect("Failed to deserialize Pool");
 
     println!("Pool exists: {}", svm.get_account(&pool_pda).is_some());
-    assert_eq!(data.liquidity, 0);
+    assert_eq!(data.liquidity, utils::get_utils::MINIMUM_LIQUIDITY);
     assert_eq!(data.token0, token0);
     assert_eq!(data.token1, token1);
 
     let (tokenliq_pda, tokenliq_bump) =
-        utils::get_utils::get_tokenliq_pda(&program_id, &admin.pubkey(), &token0, &token1);
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
 
     let depositor_token0_ata_account = CreateAssociatedTokenAccount::new(&mut svm, &admin, &token0)
         .owner(&depositor.pubkey())
@@ -144,12 +175,13 @@ fn test_successful_withdraw() {
     let tx_deposit_result = utils::handlers::handle_deposit(
         &mut svm,
         &program_id,
-        &admin,
+        &amm_config,
         &depositor,
         &token0,
         &token1,
         &amount_a,
         &amount_b,
+        &0,
     )
     .unwrap();
 
@@ -189,40 +221,164 @@ fn test_successful_withdraw() {
     let expected_token0_after_deposit = amount_a_to_mint - amount_a;
     let expected_token1_after_deposit = amount_b_to_mint - amount_b;
 
-    let expected_liquidity =
-        utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap();
+    let supply = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap();
+    let expected_liquidity = supply - utils::get_utils::MINIMUM_LIQUIDITY;
 
     assert_eq!(expected_liquidity, amount);
     assert_eq!(expected_token0_after_deposit, depositor_token0_amount);
     assert_eq!(expected_token1_after_deposit, depositor_token1_amount);
 
     let amount_to_withdraw = expected_liquidity;
+    let expected_amount0_out = amount_to_withdraw * amount_a / supply;
+    let expected_amount1_out = amount_to_withdraw * amount_b / supply;
 
     let tx_withdraw_result = utils::handlers::handle_withdraw(
         &mut svm,
         &program_id,
-        &admin,
+        &amm_config,
         &depositor,
         &token0,
         &token1,
         &amount_to_withdraw,
+        &expected_amount0_out,
+        &expected_amount1_out,
     )
     .unwrap();
 
     let account_depositor_0_ata = svm
         .get_account(&depositor_ata_token0_address)
         .expect("Should exist");
-    let token0_amount = Account::unpack(&account_ata.data)
+    let token0_amount = Account::unpack(&account_depositor_0_ata.data)
         .expect("failed to unpack token")
         .amount;
 
     let account_depositor_1_ata = svm
         .get_account(&depositor_ata_token1_address)
         .expect("Should exist");
-    let token1_amount = Account::unpack(&account_ata.data)
+    let token1_amount = Account::unpack(&account_depositor_1_ata.data)
+        .expect("failed to unpack token")
+        .amount;
+
+    assert_eq!(
+        token0_amount,
+        expected_token0_after_deposit + expected_amount0_out
+    );
+    assert_eq!(
+        token1_amount,
+        expected_token1_after_deposit + expected_amount1_out
+    );
+
+    let events: Vec<LiquidityRemoved> =
+        utils::get_utils::get_events(&tx_withdraw_result.logs, "LiquidityRemoved");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].pool, pool_pda);
+    assert_eq!(events[0].depositor, depositor.pubkey());
+    assert_eq!(events[0].amount0, expected_amount0_out);
+    assert_eq!(events[0].amount1, expected_amount1_out);
+    assert_eq!(events[0].amount0_received, expected_amount0_out);
+    assert_eq!(events[0].amount1_received, expected_amount1_out);
+    assert_eq!(events[0].liquidity_burned, amount_to_withdraw);
+    // only the locked MINIMUM_LIQUIDITY share is left
+    assert_eq!(events[0].reserve0, amount_a - expected_amount0_out);
+    assert_eq!(events[0].reserve1, amount_b - expected_amount1_out);
+}
+
+#[test]
+fn test_withdraw_slippage_exceeded() {
+    let mut svm = LiteSVM::new();
+    let admin = Keypair::new();
+    let depositor = Keypair::new();
+
+    let program_keypair = read_keypair_file("../../target/deploy/amm-keypair.json")
+        .expect("Failed to read program keypair");
+    let program_id = program_keypair.pubkey();
+    let program_bytes = include_bytes!("../target/deploy/amm.so");
+    utils::setup_utils::deploy_program(&mut svm, &program_id, program_bytes, &admin.pubkey());
+
+    svm.airdrop(&admin.pubkey(), 1_000_000_000).unwrap();
+    svm.airdrop(&depositor.pubkey(), 1_000_000_000_000).unwrap();
+
+    let fee_bps = 30;
+
+    let (amm_config, _bump) = utils::get_utils::get_amm_config_pda(&program_id, &fee_bps);
+    let amount_a = 10_000_000;
+    let amount_b = 10_000_000;
+    let (token0, token1) = utils::setup_utils::setup_pool(
+        &mut svm,
+        &program_id,
+        &admin,
+        &depositor,
+        &fee_bps,
+        &amount_a,
+        &amount_b,
+    );
+
+    let tx_deposit_result = utils::handlers::handle_deposit(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &amount_a,
+        &amount_b,
+        &0,
+    );
+    assert!(
+        tx_deposit_result.is_ok(),
+        "Deposit transaction failed: {:?}",
+        tx_deposit_result.err()
+    );
+
+    let liquidity = utils::get_utils::get_expected_liquidity(&amount_a, &amount_b).unwrap()
+        - utils::get_utils::MINIMUM_LIQUIDITY;
+
+    // Each side is checked on its own
+    let tx_withdraw_result = utils::handlers::handle_withdraw(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &liquidity,
+        &(amount_a + 1),
+        &0,
+    );
+    match tx_withdraw_result.unwrap_err().err {
+        // CustomError::InsufficientAmount0Out
+        TransactionError::InstructionError(0, InstructionError::Custom(6008)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let tx_withdraw_result = utils::handlers::handle_withdraw(
+        &mut svm,
+        &program_id,
+        &amm_config,
+        &depositor,
+        &token0,
+        &token1,
+        &liquidity,
+        &0,
+        &(amount_b + 1),
+    );
+    match tx_withdraw_result.unwrap_err().err {
+        // CustomError::InsufficientAmount1Out
+        TransactionError::InstructionError(0, InstructionError::Custom(6009)) => {}
+        err => panic!("Got unexpected error: {:?}", err),
+    }
+
+    let (tokenliq_pda, _tokenliq_bump) =
+        utils::get_utils::get_tokenliq_pda(&program_id, &amm_config, &token0, &token1);
+    let depositor_tokenliq_ata = svm
+        .get_account(&get_associated_token_address(
+            &depositor.pubkey(),
+            &tokenliq_pda,
+        ))
+        .expect("Should exist");
+    let depositor_liquidity = Account::unpack(&depositor_tokenliq_ata.data)
         .expect("failed to unpack token")
         .amount;
 
-    assert_eq!(token0_amount, 10_000_000);
-    assert_eq!(token1_amount, 10_000_000);
+    assert_eq!(depositor_liquidity, liquidity);
 }